@@ -483,6 +483,7 @@ fn main() {
         bank_forks.clone(),
         &Arc::new(PrioritizationFeeCache::new(0u64)),
         false,
+        false,
     );
 
     // This is so that the signal_receiver does not go out of scope after the closure.
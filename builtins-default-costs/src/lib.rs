@@ -205,6 +205,33 @@ lazy_static! {
     };
 }
 
+/// Memoizes whether program ids are builtins, for callers repeatedly checking
+/// the same program ids, eg. while scanning a transaction's instructions.
+/// Combines the `MAYBE_BUILTIN_KEY` first-byte filter with a full lookup, and
+/// caches the result per pubkey.
+#[derive(Debug, Default)]
+pub struct BuiltinMembership {
+    checked: Vec<(Pubkey, bool)>,
+}
+
+impl BuiltinMembership {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns true if `program_id` is a builtin, regardless of its migration status.
+    pub fn is_builtin(&mut self, program_id: &Pubkey) -> bool {
+        if let Some((_, is_builtin)) = self.checked.iter().find(|(key, _)| key == program_id) {
+            return *is_builtin;
+        }
+
+        let is_builtin = MAYBE_BUILTIN_KEY[program_id.as_ref()[0] as usize]
+            && BUILTIN_INSTRUCTION_COSTS.contains_key(program_id);
+        self.checked.push((*program_id, is_builtin));
+        is_builtin
+    }
+}
+
 pub fn get_builtin_instruction_cost<'a>(
     program_id: &'a Pubkey,
     feature_set: &'a FeatureSet,
@@ -215,6 +242,16 @@ pub fn get_builtin_instruction_cost<'a>(
         .map(|builtin_cost| builtin_cost.native_cost())
 }
 
+/// Returns the default compute units charged for an instruction of builtin
+/// program `program_id`, or `None` if it is not a builtin. Unlike
+/// `get_builtin_instruction_cost`, this does not consider whether the builtin
+/// has been migrated to core bpf.
+pub fn default_compute_units(program_id: &Pubkey) -> Option<u64> {
+    BUILTIN_INSTRUCTION_COSTS
+        .get(program_id)
+        .map(|builtin_cost| builtin_cost.native_cost())
+}
+
 pub enum BuiltinMigrationFeatureIndex {
     NotBuiltin,
     BuiltinNoMigrationFeature,
@@ -316,6 +353,45 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_default_compute_units() {
+        assert_eq!(
+            Some(solana_compute_budget_program::DEFAULT_COMPUTE_UNITS),
+            default_compute_units(&compute_budget::id())
+        );
+        assert_eq!(
+            Some(solana_system_program::system_processor::DEFAULT_COMPUTE_UNITS),
+            default_compute_units(&system_program::id())
+        );
+        // migrating builtins report their native cost regardless of feature status
+        assert_eq!(
+            Some(solana_stake_program::stake_instruction::DEFAULT_COMPUTE_UNITS),
+            default_compute_units(&stake::id())
+        );
+        // precompiles are not charged
+        assert_eq!(Some(0), default_compute_units(&ed25519_program::id()));
+
+        assert!(default_compute_units(&Pubkey::new_unique()).is_none());
+    }
+
+    #[test]
+    fn test_builtin_membership() {
+        let mut builtin_membership = BuiltinMembership::new();
+        let not_builtin = Pubkey::new_unique();
+
+        assert!(builtin_membership.is_builtin(&system_program::id()));
+        assert!(builtin_membership.is_builtin(&stake::id()));
+        assert!(!builtin_membership.is_builtin(&not_builtin));
+        assert_eq!(builtin_membership.checked.len(), 3);
+
+        // repeated lookups are served from the cache
+        for _ in 0..3 {
+            assert!(builtin_membership.is_builtin(&system_program::id()));
+            assert!(!builtin_membership.is_builtin(&not_builtin));
+        }
+        assert_eq!(builtin_membership.checked.len(), 3);
+    }
+
     #[test]
     fn test_get_builtin_migration_feature_index() {
         assert!(matches!(
@@ -4,7 +4,9 @@ use {
         compute_budget_program_id_filter::ComputeBudgetProgramIdFilter,
     },
     solana_borsh::v1::try_from_slice_unchecked,
-    solana_builtins_default_costs::{get_migration_feature_id, MIGRATING_BUILTINS_COSTS},
+    solana_builtins_default_costs::{
+        get_builtin_instruction_cost, get_migration_feature_id, MIGRATING_BUILTINS_COSTS,
+    },
     solana_compute_budget::compute_budget_limits::*,
     solana_compute_budget_interface::ComputeBudgetInstruction,
     solana_feature_set::{self as feature_set, FeatureSet},
@@ -98,6 +100,11 @@ impl ComputeBudgetInstructionDetails {
         Ok(compute_budget_instruction_details)
     }
 
+    /// Returns index and value of the transaction's `SetComputeUnitPrice` instruction, if any.
+    pub fn requested_compute_unit_price(&self) -> Option<(u8, u64)> {
+        self.requested_compute_unit_price
+    }
+
     pub fn sanitize_and_convert_to_compute_budget_limits(
         &self,
         feature_set: &FeatureSet,
@@ -137,8 +144,20 @@ impl ComputeBudgetInstructionDetails {
             if let Some((_index, requested_loaded_accounts_data_size_limit)) =
                 self.requested_loaded_accounts_data_size_limit
             {
-                NonZeroU32::new(requested_loaded_accounts_data_size_limit)
-                    .ok_or(TransactionError::InvalidLoadedAccountsDataSizeLimit)?
+                match NonZeroU32::new(requested_loaded_accounts_data_size_limit) {
+                    Some(requested_loaded_accounts_data_size_limit) => {
+                        requested_loaded_accounts_data_size_limit
+                    }
+                    // zero is interpreted as "use default" once the feature is active,
+                    // otherwise it is rejected.
+                    None if feature_set.is_active(
+                        &feature_set::default_zero_loaded_accounts_data_size_limit::id(),
+                    ) =>
+                    {
+                        MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES
+                    }
+                    None => return Err(TransactionError::InvalidLoadedAccountsDataSizeLimit),
+                }
             } else {
                 MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES
             }
@@ -152,6 +171,109 @@ impl ComputeBudgetInstructionDetails {
         })
     }
 
+    /// Returns ids of feature gates whose activation changes this transaction's compute budget
+    /// limits, regardless of their current activation state.
+    pub fn relevant_feature_gates(&self) -> Vec<Pubkey> {
+        let mut feature_gates = vec![];
+
+        if self.requested_compute_unit_limit.is_none() {
+            let default_compute_unit_limit = |feature_set: &FeatureSet| {
+                self.calculate_default_compute_unit_limit(feature_set)
+                    .min(MAX_COMPUTE_UNIT_LIMIT)
+            };
+
+            let mut feature_set = FeatureSet::default();
+            let compute_unit_limit = default_compute_unit_limit(&feature_set);
+            feature_set.activate(
+                &feature_set::reserve_minimal_cus_for_builtin_instructions::id(),
+                0,
+            );
+            let reserved_compute_unit_limit = default_compute_unit_limit(&feature_set);
+            if compute_unit_limit != reserved_compute_unit_limit {
+                feature_gates.push(feature_set::reserve_minimal_cus_for_builtin_instructions::id());
+            }
+
+            // migrating builtins are only accounted separately once minimal CUs are reserved
+            // for builtin instructions
+            for (index, count) in self
+                .migrating_builtin_feature_counters
+                .migrating_builtin
+                .iter()
+                .enumerate()
+            {
+                if count.0 == 0 {
+                    continue;
+                }
+                let migration_feature_id = get_migration_feature_id(index);
+                let mut migrated_feature_set = feature_set.clone();
+                migrated_feature_set.activate(migration_feature_id, 0);
+                if default_compute_unit_limit(&migrated_feature_set) != reserved_compute_unit_limit
+                {
+                    feature_gates.push(*migration_feature_id);
+                }
+            }
+        }
+
+        if matches!(
+            self.requested_loaded_accounts_data_size_limit,
+            Some((_index, 0))
+        ) {
+            feature_gates.push(feature_set::default_zero_loaded_accounts_data_size_limit::id());
+        }
+
+        feature_gates
+    }
+
+    /// Returns false if the transaction's effective compute unit limit can not cover the cost
+    /// of its builtin instructions, such transaction will fail, or if its compute budget
+    /// instructions fail to sanitize.
+    pub fn cu_limit_covers_builtins<'a>(
+        &self,
+        instructions: impl Iterator<Item = (&'a Pubkey, SVMInstruction<'a>)>,
+        feature_set: &FeatureSet,
+    ) -> bool {
+        let Ok(compute_budget_limits) =
+            self.sanitize_and_convert_to_compute_budget_limits(feature_set)
+        else {
+            return false;
+        };
+
+        u64::from(compute_budget_limits.compute_unit_limit)
+            >= Self::sum_builtin_compute_units(instructions, feature_set)
+    }
+
+    /// Returns the compute unit limit the runtime effectively allows the transaction, that is
+    /// the requested or default limit, raised to the cost of its builtin instructions if that is
+    /// higher, and clamped to `MAX_COMPUTE_UNIT_LIMIT`.
+    pub fn runtime_effective_cu_limit<'a>(
+        &self,
+        instructions: impl Iterator<Item = (&'a Pubkey, SVMInstruction<'a>)>,
+        feature_set: &FeatureSet,
+    ) -> u32 {
+        let compute_unit_limit = self.requested_compute_unit_limit.map_or_else(
+            || self.calculate_default_compute_unit_limit(feature_set),
+            |(_index, requested_compute_unit_limit)| requested_compute_unit_limit,
+        );
+        let sum_builtin_compute_units =
+            u32::try_from(Self::sum_builtin_compute_units(instructions, feature_set))
+                .unwrap_or(u32::MAX);
+
+        compute_unit_limit
+            .max(sum_builtin_compute_units)
+            .min(MAX_COMPUTE_UNIT_LIMIT)
+    }
+
+    fn sum_builtin_compute_units<'a>(
+        instructions: impl Iterator<Item = (&'a Pubkey, SVMInstruction<'a>)>,
+        feature_set: &FeatureSet,
+    ) -> u64 {
+        instructions
+            .filter_map(|(program_id, _instruction)| {
+                get_builtin_instruction_cost(program_id, feature_set)
+            })
+            .fold(0u64, u64::saturating_add)
+    }
+
     fn process_instruction(&mut self, index: u8, instruction: &SVMInstruction) -> Result<()> {
         let invalid_instruction_data_error =
             TransactionError::InstructionError(index, InstructionError::InvalidInstructionData);
@@ -235,6 +357,7 @@ mod test {
         solana_pubkey::Pubkey,
         solana_signer::Signer,
         solana_svm_transaction::svm_message::SVMMessage,
+        solana_system_interface::instruction::transfer,
         solana_transaction::{sanitized::SanitizedTransaction, Transaction},
     };
 
@@ -364,6 +487,95 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_sanitize_zero_loaded_accounts_data_size_limit() {
+        let instruction_details = ComputeBudgetInstructionDetails {
+            requested_compute_unit_limit: Some((1, 1_000)),
+            requested_loaded_accounts_data_size_limit: Some((2, 0)),
+            ..ComputeBudgetInstructionDetails::default()
+        };
+
+        // zero is rejected while feature is inactive
+        let mut feature_set = FeatureSet::default();
+        assert_eq!(
+            instruction_details.sanitize_and_convert_to_compute_budget_limits(&feature_set),
+            Err(TransactionError::InvalidLoadedAccountsDataSizeLimit)
+        );
+
+        // zero is interpreted as default limit while feature is active
+        feature_set.activate(
+            &feature_set::default_zero_loaded_accounts_data_size_limit::id(),
+            0,
+        );
+        assert_eq!(
+            instruction_details.sanitize_and_convert_to_compute_budget_limits(&feature_set),
+            Ok(ComputeBudgetLimits {
+                compute_unit_limit: 1_000,
+                loaded_accounts_bytes: MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES,
+                ..ComputeBudgetLimits::default()
+            })
+        );
+    }
+
+    #[test]
+    fn test_relevant_feature_gates() {
+        let get_relevant_feature_gates = |instructions: &[Instruction]| {
+            let tx = build_sanitized_transaction(instructions);
+            ComputeBudgetInstructionDetails::try_from(SVMMessage::program_instructions_iter(&tx))
+                .unwrap()
+                .relevant_feature_gates()
+        };
+
+        // no builtin instruction, default compute unit limit is not affected
+        assert!(get_relevant_feature_gates(&[Instruction::new_with_bincode(
+            Pubkey::new_unique(),
+            &(),
+            vec![]
+        )])
+        .is_empty());
+
+        // default compute unit limit with builtin instruction
+        assert_eq!(
+            get_relevant_feature_gates(&[
+                Instruction::new_with_bincode(Pubkey::new_unique(), &(), vec![]),
+                transfer(&Pubkey::new_unique(), &Pubkey::new_unique(), 1),
+            ]),
+            vec![feature_set::reserve_minimal_cus_for_builtin_instructions::id()]
+        );
+
+        // default compute unit limit with migrating builtin instruction
+        assert_eq!(
+            get_relevant_feature_gates(&[
+                Instruction::new_with_bincode(Pubkey::new_unique(), &(), vec![]),
+                solana_program::stake::instruction::delegate_stake(
+                    &Pubkey::new_unique(),
+                    &Pubkey::new_unique(),
+                    &Pubkey::new_unique(),
+                ),
+            ]),
+            vec![
+                feature_set::reserve_minimal_cus_for_builtin_instructions::id(),
+                feature_set::migrate_stake_program_to_core_bpf::id(),
+            ]
+        );
+
+        // requested compute unit limit is not affected
+        assert!(get_relevant_feature_gates(&[
+            ComputeBudgetInstruction::set_compute_unit_limit(1_000),
+            transfer(&Pubkey::new_unique(), &Pubkey::new_unique(), 1),
+        ])
+        .is_empty());
+
+        // zero loaded accounts data size limit
+        assert_eq!(
+            get_relevant_feature_gates(&[
+                ComputeBudgetInstruction::set_compute_unit_limit(1_000),
+                ComputeBudgetInstruction::set_loaded_accounts_data_size_limit(0),
+            ]),
+            vec![feature_set::default_zero_loaded_accounts_data_size_limit::id()]
+        );
+    }
+
     fn prep_feature_minimial_cus_for_builtin_instructions(
         is_active: bool,
         instruction_details: &ComputeBudgetInstructionDetails,
@@ -641,4 +853,96 @@ mod test {
             })
         );
     }
+
+    #[test]
+    fn test_cu_limit_covers_builtins() {
+        let feature_set = FeatureSet::default();
+        // compute budget and system programs are builtins, each costs 150 CUs
+        let builtins_cost = 300;
+
+        for (compute_unit_limit, expected) in [
+            (builtins_cost - 1, false),
+            (builtins_cost, true),
+            (builtins_cost + 1, true),
+        ] {
+            let tx = build_sanitized_transaction(&[
+                ComputeBudgetInstruction::set_compute_unit_limit(compute_unit_limit),
+                transfer(&Pubkey::new_unique(), &Pubkey::new_unique(), 1),
+            ]);
+            let details = ComputeBudgetInstructionDetails::try_from(
+                SVMMessage::program_instructions_iter(&tx),
+            )
+            .unwrap();
+            assert_eq!(
+                details.cu_limit_covers_builtins(
+                    SVMMessage::program_instructions_iter(&tx),
+                    &feature_set
+                ),
+                expected
+            );
+        }
+
+        // default compute unit limit covers builtins
+        let tx = build_sanitized_transaction(&[transfer(
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            1,
+        )]);
+        let details =
+            ComputeBudgetInstructionDetails::try_from(SVMMessage::program_instructions_iter(&tx))
+                .unwrap();
+        assert!(details
+            .cu_limit_covers_builtins(SVMMessage::program_instructions_iter(&tx), &feature_set));
+    }
+
+    #[test]
+    fn test_runtime_effective_cu_limit() {
+        let feature_set = FeatureSet::default();
+        // compute budget and system programs are builtins, each costs 150 CUs
+        let builtins_cost = 300;
+
+        for (compute_unit_limit, expected) in [
+            (0, builtins_cost),
+            (builtins_cost - 1, builtins_cost),
+            (builtins_cost + 1, builtins_cost + 1),
+            (u32::MAX, MAX_COMPUTE_UNIT_LIMIT),
+        ] {
+            let tx = build_sanitized_transaction(&[
+                ComputeBudgetInstruction::set_compute_unit_limit(compute_unit_limit),
+                transfer(&Pubkey::new_unique(), &Pubkey::new_unique(), 1),
+            ]);
+            let details = ComputeBudgetInstructionDetails::try_from(
+                SVMMessage::program_instructions_iter(&tx),
+            )
+            .unwrap();
+            assert_eq!(
+                details.runtime_effective_cu_limit(
+                    SVMMessage::program_instructions_iter(&tx),
+                    &feature_set
+                ),
+                expected,
+                "requested compute unit limit {compute_unit_limit}"
+            );
+        }
+
+        // without requested limit, the default limit already covers builtins
+        let tx = build_sanitized_transaction(&[transfer(
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            1,
+        )]);
+        let details =
+            ComputeBudgetInstructionDetails::try_from(SVMMessage::program_instructions_iter(&tx))
+                .unwrap();
+        assert_eq!(
+            details.runtime_effective_cu_limit(
+                SVMMessage::program_instructions_iter(&tx),
+                &feature_set
+            ),
+            details
+                .sanitize_and_convert_to_compute_budget_limits(&feature_set)
+                .unwrap()
+                .compute_unit_limit
+        );
+    }
 }
@@ -1,10 +1,57 @@
 use {
     crate::compute_budget_instruction_details::*, solana_compute_budget::compute_budget_limits::*,
-    solana_feature_set::FeatureSet, solana_pubkey::Pubkey,
-    solana_svm_transaction::instruction::SVMInstruction,
+    solana_feature_set::FeatureSet, solana_instruction::error::InstructionError,
+    solana_pubkey::Pubkey, solana_svm_transaction::instruction::SVMInstruction,
     solana_transaction_error::TransactionError,
 };
 
+/// Additional restrictions applied when processing compute budget instructions.
+/// Default config imposes no restriction beyond the protocol's.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ComputeBudgetParsingConfig {
+    /// If set, a requested compute unit price above this value fails the transaction
+    /// with `InvalidInstructionData` on the offending instruction.
+    pub max_compute_unit_price: Option<u64>,
+    /// If set, a transaction with more instructions than this value fails with
+    /// `SanitizeFailure`. Not enforced by the protocol, only leader and RPC callers
+    /// should enable it.
+    pub max_instructions: Option<usize>,
+}
+
+/// Tally of compute budget instruction parsing rejections, for telemetry.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ParseStats {
+    /// Number of transactions rejected with `DuplicateInstruction`.
+    pub num_duplicate_instructions: u64,
+    /// Number of transactions rejected with `InvalidInstructionData`.
+    pub num_invalid_instruction_data: u64,
+}
+
+/// Parses compute budget instructions into `ComputeBudgetInstructionDetails`,
+/// tallying rejections into `stats` if provided.
+pub fn get_instruction_details_with_stats<'a>(
+    instructions: impl Iterator<Item = (&'a Pubkey, SVMInstruction<'a>)> + Clone,
+    stats: Option<&mut ParseStats>,
+) -> Result<ComputeBudgetInstructionDetails, TransactionError> {
+    let result = ComputeBudgetInstructionDetails::try_from(instructions);
+
+    if let (Err(err), Some(stats)) = (&result, stats) {
+        match err {
+            TransactionError::DuplicateInstruction(_) => {
+                stats.num_duplicate_instructions =
+                    stats.num_duplicate_instructions.saturating_add(1)
+            }
+            TransactionError::InstructionError(_, InstructionError::InvalidInstructionData) => {
+                stats.num_invalid_instruction_data =
+                    stats.num_invalid_instruction_data.saturating_add(1)
+            }
+            _ => {}
+        }
+    }
+
+    result
+}
+
 /// Processing compute_budget could be part of tx sanitizing, failed to process
 /// these instructions will drop the transaction eventually without execution,
 /// may as well fail it early.
@@ -14,8 +61,41 @@ pub fn process_compute_budget_instructions<'a>(
     instructions: impl Iterator<Item = (&'a Pubkey, SVMInstruction<'a>)> + Clone,
     feature_set: &FeatureSet,
 ) -> Result<ComputeBudgetLimits, TransactionError> {
-    ComputeBudgetInstructionDetails::try_from(instructions)?
-        .sanitize_and_convert_to_compute_budget_limits(feature_set)
+    process_compute_budget_instructions_with_config(
+        instructions,
+        feature_set,
+        &ComputeBudgetParsingConfig::default(),
+    )
+}
+
+/// Same as `process_compute_budget_instructions`, additionally enforces restrictions
+/// specified in `config`.
+pub fn process_compute_budget_instructions_with_config<'a>(
+    instructions: impl Iterator<Item = (&'a Pubkey, SVMInstruction<'a>)> + Clone,
+    feature_set: &FeatureSet,
+    config: &ComputeBudgetParsingConfig,
+) -> Result<ComputeBudgetLimits, TransactionError> {
+    if let Some(max_instructions) = config.max_instructions {
+        if instructions.clone().count() > max_instructions {
+            return Err(TransactionError::SanitizeFailure);
+        }
+    }
+
+    let details = ComputeBudgetInstructionDetails::try_from(instructions)?;
+
+    if let (Some(max_compute_unit_price), Some((index, requested_compute_unit_price))) = (
+        config.max_compute_unit_price,
+        details.requested_compute_unit_price(),
+    ) {
+        if requested_compute_unit_price > max_compute_unit_price {
+            return Err(TransactionError::InstructionError(
+                index,
+                InstructionError::InvalidInstructionData,
+            ));
+        }
+    }
+
+    details.sanitize_and_convert_to_compute_budget_limits(feature_set)
 }
 
 #[cfg(test)]
@@ -24,7 +104,7 @@ mod tests {
         super::*,
         solana_compute_budget_interface::ComputeBudgetInstruction,
         solana_hash::Hash,
-        solana_instruction::{error::InstructionError, Instruction},
+        solana_instruction::Instruction,
         solana_keypair::Keypair,
         solana_message::Message,
         solana_pubkey::Pubkey,
@@ -446,4 +526,207 @@ mod tests {
             assert_eq!(result, expected_result);
         }
     }
+
+    #[test]
+    fn test_process_instructions_with_max_compute_unit_price() {
+        let payer_keypair = Keypair::new();
+        let max_compute_unit_price = 1_000;
+        let config = ComputeBudgetParsingConfig {
+            max_compute_unit_price: Some(max_compute_unit_price),
+            ..ComputeBudgetParsingConfig::default()
+        };
+
+        for (compute_unit_price, config, expected_result) in [
+            // no ceiling by default
+            (
+                u64::MAX,
+                ComputeBudgetParsingConfig::default(),
+                Ok(ComputeBudgetLimits {
+                    compute_unit_limit: 1,
+                    compute_unit_price: u64::MAX,
+                    ..ComputeBudgetLimits::default()
+                }),
+            ),
+            // at and below ceiling
+            (
+                max_compute_unit_price,
+                config,
+                Ok(ComputeBudgetLimits {
+                    compute_unit_limit: 1,
+                    compute_unit_price: max_compute_unit_price,
+                    ..ComputeBudgetLimits::default()
+                }),
+            ),
+            (
+                max_compute_unit_price - 1,
+                config,
+                Ok(ComputeBudgetLimits {
+                    compute_unit_limit: 1,
+                    compute_unit_price: max_compute_unit_price - 1,
+                    ..ComputeBudgetLimits::default()
+                }),
+            ),
+            // above ceiling
+            (
+                max_compute_unit_price + 1,
+                config,
+                Err(TransactionError::InstructionError(
+                    2,
+                    InstructionError::InvalidInstructionData,
+                )),
+            ),
+            (
+                u64::MAX,
+                config,
+                Err(TransactionError::InstructionError(
+                    2,
+                    InstructionError::InvalidInstructionData,
+                )),
+            ),
+        ] {
+            let transaction = SanitizedTransaction::from_transaction_for_tests(
+                Transaction::new_signed_with_payer(
+                    &[
+                        Instruction::new_with_bincode(Pubkey::new_unique(), &0_u8, vec![]),
+                        ComputeBudgetInstruction::set_compute_unit_limit(1),
+                        ComputeBudgetInstruction::set_compute_unit_price(compute_unit_price),
+                    ],
+                    Some(&payer_keypair.pubkey()),
+                    &[&payer_keypair],
+                    Hash::default(),
+                ),
+            );
+
+            for feature_set in [FeatureSet::default(), FeatureSet::all_enabled()] {
+                let result = process_compute_budget_instructions_with_config(
+                    SVMMessage::program_instructions_iter(&transaction),
+                    &feature_set,
+                    &config,
+                );
+                assert_eq!(result, expected_result);
+            }
+        }
+    }
+
+    #[test]
+    fn test_process_instructions_with_max_instructions() {
+        let payer_keypair = Keypair::new();
+        let max_instructions = 4;
+        let config = ComputeBudgetParsingConfig {
+            max_instructions: Some(max_instructions),
+            ..ComputeBudgetParsingConfig::default()
+        };
+
+        for (num_instructions, config, expected_result) in [
+            // no limit by default
+            (
+                max_instructions + 1,
+                ComputeBudgetParsingConfig::default(),
+                Ok(ComputeBudgetLimits {
+                    compute_unit_limit: 1,
+                    ..ComputeBudgetLimits::default()
+                }),
+            ),
+            // at limit
+            (
+                max_instructions,
+                config,
+                Ok(ComputeBudgetLimits {
+                    compute_unit_limit: 1,
+                    ..ComputeBudgetLimits::default()
+                }),
+            ),
+            // above limit
+            (
+                max_instructions + 1,
+                config,
+                Err(TransactionError::SanitizeFailure),
+            ),
+        ] {
+            let mut instructions = vec![ComputeBudgetInstruction::set_compute_unit_limit(1)];
+            instructions.resize_with(num_instructions, || {
+                Instruction::new_with_bincode(Pubkey::new_unique(), &0_u8, vec![])
+            });
+            let transaction = SanitizedTransaction::from_transaction_for_tests(
+                Transaction::new_signed_with_payer(
+                    &instructions,
+                    Some(&payer_keypair.pubkey()),
+                    &[&payer_keypair],
+                    Hash::default(),
+                ),
+            );
+
+            for feature_set in [FeatureSet::default(), FeatureSet::all_enabled()] {
+                let result = process_compute_budget_instructions_with_config(
+                    SVMMessage::program_instructions_iter(&transaction),
+                    &feature_set,
+                    &config,
+                );
+                assert_eq!(result, expected_result);
+            }
+        }
+    }
+
+    #[test]
+    fn test_get_instruction_details_with_stats() {
+        let payer_keypair = Keypair::new();
+        let transactions = [
+            // valid
+            vec![
+                transfer(&payer_keypair.pubkey(), &Pubkey::new_unique(), 1),
+                ComputeBudgetInstruction::set_compute_unit_price(1),
+            ],
+            // duplicate price
+            vec![
+                ComputeBudgetInstruction::set_compute_unit_price(1),
+                ComputeBudgetInstruction::set_compute_unit_price(2),
+            ],
+            // duplicate limit
+            vec![
+                ComputeBudgetInstruction::set_compute_unit_limit(1),
+                ComputeBudgetInstruction::set_compute_unit_limit(2),
+            ],
+            // invalid data
+            vec![Instruction::new_with_bincode(
+                solana_sdk_ids::compute_budget::id(),
+                &0_u8,
+                vec![],
+            )],
+        ]
+        .map(|instructions| {
+            SanitizedTransaction::from_transaction_for_tests(Transaction::new_signed_with_payer(
+                &instructions,
+                Some(&payer_keypair.pubkey()),
+                &[&payer_keypair],
+                Hash::default(),
+            ))
+        });
+
+        let mut stats = ParseStats::default();
+        let results: Vec<_> = transactions
+            .iter()
+            .map(|transaction| {
+                get_instruction_details_with_stats(
+                    SVMMessage::program_instructions_iter(transaction),
+                    Some(&mut stats),
+                )
+                .is_ok()
+            })
+            .collect();
+        assert_eq!(results, vec![true, false, false, false]);
+        assert_eq!(
+            stats,
+            ParseStats {
+                num_duplicate_instructions: 2,
+                num_invalid_instruction_data: 1,
+            }
+        );
+
+        // stats are optional
+        assert!(get_instruction_details_with_stats(
+            SVMMessage::program_instructions_iter(&transactions[1]),
+            None,
+        )
+        .is_err());
+    }
 }
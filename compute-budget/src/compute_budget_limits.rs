@@ -24,6 +24,10 @@ const MICRO_LAMPORTS_PER_LAMPORT: u64 = 1_000_000;
 pub const MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES: NonZeroU32 =
     unsafe { NonZeroU32::new_unchecked(64 * 1024 * 1024) };
 
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_derive::Deserialize, serde_derive::Serialize)
+)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct ComputeBudgetLimits {
     pub updated_heap_bytes: u32,
@@ -43,6 +47,71 @@ impl Default for ComputeBudgetLimits {
     }
 }
 
+impl ComputeBudgetLimits {
+    /// Returns a builder starting from default limits, only fields being set are overridden.
+    pub fn builder() -> ComputeBudgetLimitsBuilder {
+        ComputeBudgetLimitsBuilder::default()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComputeBudgetLimitsError {
+    /// loaded accounts bytes must be non-zero
+    ZeroLoadedAccountsBytes,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ComputeBudgetLimitsBuilder {
+    updated_heap_bytes: Option<u32>,
+    compute_unit_limit: Option<u32>,
+    compute_unit_price: Option<u64>,
+    loaded_accounts_bytes: Option<u32>,
+}
+
+impl ComputeBudgetLimitsBuilder {
+    pub fn updated_heap_bytes(mut self, updated_heap_bytes: u32) -> Self {
+        self.updated_heap_bytes = Some(updated_heap_bytes);
+        self
+    }
+
+    pub fn compute_unit_limit(mut self, compute_unit_limit: u32) -> Self {
+        self.compute_unit_limit = Some(compute_unit_limit);
+        self
+    }
+
+    pub fn compute_unit_price(mut self, compute_unit_price: u64) -> Self {
+        self.compute_unit_price = Some(compute_unit_price);
+        self
+    }
+
+    pub fn loaded_accounts_bytes(mut self, loaded_accounts_bytes: u32) -> Self {
+        self.loaded_accounts_bytes = Some(loaded_accounts_bytes);
+        self
+    }
+
+    pub fn build(self) -> Result<ComputeBudgetLimits, ComputeBudgetLimitsError> {
+        let default = ComputeBudgetLimits::default();
+        let loaded_accounts_bytes = match self.loaded_accounts_bytes {
+            Some(loaded_accounts_bytes) => NonZeroU32::new(loaded_accounts_bytes)
+                .ok_or(ComputeBudgetLimitsError::ZeroLoadedAccountsBytes)?,
+            None => default.loaded_accounts_bytes,
+        };
+
+        Ok(ComputeBudgetLimits {
+            updated_heap_bytes: self
+                .updated_heap_bytes
+                .unwrap_or(default.updated_heap_bytes),
+            compute_unit_limit: self
+                .compute_unit_limit
+                .unwrap_or(default.compute_unit_limit),
+            compute_unit_price: self
+                .compute_unit_price
+                .unwrap_or(default.compute_unit_price),
+            loaded_accounts_bytes,
+        })
+    }
+}
+
 fn get_prioritization_fee(compute_unit_price: u64, compute_unit_limit: u64) -> u64 {
     let micro_lamport_fee: MicroLamports =
         (compute_unit_price as u128).saturating_mul(compute_unit_limit as u128);
@@ -53,6 +122,20 @@ fn get_prioritization_fee(compute_unit_price: u64, compute_unit_limit: u64) -> u
         .unwrap_or(u64::MAX)
 }
 
+/// Returns the compute unit price, in micro-lamports, at which a transaction
+/// requesting `compute_unit_limit` pays a prioritization fee of
+/// `total_fee_lamports`. The price is rounded down, so the resulting fee never
+/// exceeds the target; it is exact when the target is divisible by the limit.
+/// Returns 0 if `compute_unit_limit` is 0, and saturates at `u64::MAX`.
+pub fn break_even_cu_price(total_fee_lamports: u64, compute_unit_limit: u32) -> u64 {
+    let micro_lamport_fee: MicroLamports =
+        (total_fee_lamports as u128).saturating_mul(MICRO_LAMPORTS_PER_LAMPORT as u128);
+    micro_lamport_fee
+        .checked_div(compute_unit_limit as u128)
+        .map(|price| u64::try_from(price).unwrap_or(u64::MAX))
+        .unwrap_or_default()
+}
+
 impl From<ComputeBudgetLimits> for FeeBudgetLimits {
     fn from(val: ComputeBudgetLimits) -> Self {
         let prioritization_fee =
@@ -103,4 +186,94 @@ mod test {
 
         assert_eq!(get_prioritization_fee(u64::MAX, u64::MAX), u64::MAX);
     }
+
+    #[test]
+    fn test_break_even_cu_price() {
+        // exactly divisible
+        assert_eq!(break_even_cu_price(1_000, 200_000), 5_000);
+        assert_eq!(get_prioritization_fee(5_000, 200_000), 1_000);
+
+        // not divisible, rounds price down so fee does not exceed target
+        assert_eq!(break_even_cu_price(1, 3), 333_333);
+        assert_eq!(get_prioritization_fee(333_333, 3), 1);
+        assert_eq!(break_even_cu_price(10, 7), 1_428_571);
+        assert_eq!(get_prioritization_fee(1_428_571, 7), 10);
+
+        assert_eq!(break_even_cu_price(0, 200_000), 0);
+        assert_eq!(break_even_cu_price(1_000, 0), 0);
+        assert_eq!(break_even_cu_price(u64::MAX, 1), u64::MAX);
+    }
+
+    #[test]
+    fn test_compute_budget_limits_builder() {
+        assert_eq!(
+            ComputeBudgetLimits::builder().build(),
+            Ok(ComputeBudgetLimits::default())
+        );
+
+        // unset fields are default
+        assert_eq!(
+            ComputeBudgetLimits::builder()
+                .compute_unit_price(42)
+                .build(),
+            Ok(ComputeBudgetLimits {
+                updated_heap_bytes: MIN_HEAP_FRAME_BYTES,
+                compute_unit_limit: MAX_COMPUTE_UNIT_LIMIT,
+                compute_unit_price: 42,
+                loaded_accounts_bytes: MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES,
+            })
+        );
+
+        assert_eq!(
+            ComputeBudgetLimits::builder()
+                .updated_heap_bytes(MAX_HEAP_FRAME_BYTES)
+                .compute_unit_limit(1_000)
+                .compute_unit_price(42)
+                .loaded_accounts_bytes(1)
+                .build(),
+            Ok(ComputeBudgetLimits {
+                updated_heap_bytes: MAX_HEAP_FRAME_BYTES,
+                compute_unit_limit: 1_000,
+                compute_unit_price: 42,
+                loaded_accounts_bytes: NonZeroU32::new(1).unwrap(),
+            })
+        );
+
+        assert_eq!(
+            ComputeBudgetLimits::builder()
+                .loaded_accounts_bytes(0)
+                .build(),
+            Err(ComputeBudgetLimitsError::ZeroLoadedAccountsBytes)
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_compute_budget_limits_serde_round_trip() {
+        for compute_budget_limits in [
+            ComputeBudgetLimits::default(),
+            ComputeBudgetLimits {
+                updated_heap_bytes: MAX_HEAP_FRAME_BYTES,
+                compute_unit_limit: 42_000,
+                compute_unit_price: 1_234,
+                loaded_accounts_bytes: NonZeroU32::new(1).unwrap(),
+            },
+            ComputeBudgetLimits {
+                updated_heap_bytes: u32::MAX,
+                compute_unit_limit: u32::MAX,
+                compute_unit_price: u64::MAX,
+                loaded_accounts_bytes: NonZeroU32::MAX,
+            },
+        ] {
+            let serialized = bincode::serialize(&compute_budget_limits).unwrap();
+            let deserialized: ComputeBudgetLimits = bincode::deserialize(&serialized).unwrap();
+            assert_eq!(compute_budget_limits, deserialized);
+        }
+
+        // zero `loaded_accounts_bytes` is rejected
+        let mut serialized = bincode::serialize(&ComputeBudgetLimits::default()).unwrap();
+        let len = serialized.len();
+        serialized[len - 4..].copy_from_slice(&0u32.to_le_bytes());
+        assert!(bincode::deserialize::<ComputeBudgetLimits>(&serialized).is_err());
+    }
 }
@@ -823,6 +823,7 @@ impl BankingSimulator {
             bank_forks.clone(),
             prioritization_fee_cache,
             false,
+            false,
         );
 
         let (&_slot, &raw_base_event_time) = freeze_time_by_slot
@@ -81,6 +81,10 @@ mod read_write_account_set;
 mod scheduler_messages;
 mod transaction_scheduler;
 
+pub use immutable_deserialized_packet::{
+    estimate_priority_fee_from_packet, DeserializedPacketError,
+};
+
 // Fixed thread size seems to be fastest on GCP setup
 pub const NUM_THREADS: u32 = 6;
 
@@ -363,6 +367,7 @@ impl BankingStage {
         bank_forks: Arc<RwLock<BankForks>>,
         prioritization_fee_cache: &Arc<PrioritizationFeeCache>,
         enable_forwarding: bool,
+        order_by_total_priority_fee: bool,
     ) -> Self {
         Self::new_num_threads(
             block_production_method,
@@ -379,6 +384,7 @@ impl BankingStage {
             bank_forks,
             prioritization_fee_cache,
             enable_forwarding,
+            order_by_total_priority_fee,
         )
     }
 
@@ -398,6 +404,7 @@ impl BankingStage {
         bank_forks: Arc<RwLock<BankForks>>,
         prioritization_fee_cache: &Arc<PrioritizationFeeCache>,
         enable_forwarding: bool,
+        order_by_total_priority_fee: bool,
     ) -> Self {
         match block_production_method {
             BlockProductionMethod::CentralScheduler => Self::new_central_scheduler(
@@ -414,6 +421,7 @@ impl BankingStage {
                 bank_forks,
                 prioritization_fee_cache,
                 enable_forwarding,
+                order_by_total_priority_fee,
             ),
         }
     }
@@ -433,6 +441,7 @@ impl BankingStage {
         bank_forks: Arc<RwLock<BankForks>>,
         prioritization_fee_cache: &Arc<PrioritizationFeeCache>,
         enable_forwarding: bool,
+        order_by_total_priority_fee: bool,
     ) -> Self {
         assert!(num_threads >= MIN_TOTAL_THREADS);
         // Single thread to generate entries from many banks.
@@ -533,6 +542,7 @@ impl BankingStage {
                 packet_deserializer,
                 bank_forks.clone(),
                 forwarder.is_some(),
+                order_by_total_priority_fee,
             );
             let scheduler = PrioGraphScheduler::new(
                 work_senders,
@@ -832,6 +842,7 @@ mod tests {
                 bank_forks,
                 &Arc::new(PrioritizationFeeCache::new(0u64)),
                 false,
+                false,
             );
             drop(non_vote_sender);
             drop(tpu_vote_sender);
@@ -892,6 +903,7 @@ mod tests {
                 bank_forks,
                 &Arc::new(PrioritizationFeeCache::new(0u64)),
                 false,
+                false,
             );
             trace!("sending bank");
             drop(non_vote_sender);
@@ -976,6 +988,7 @@ mod tests {
                 bank_forks.clone(), // keep a local-copy of bank-forks so worker threads do not lose weak access to bank-forks
                 &Arc::new(PrioritizationFeeCache::new(0u64)),
                 false,
+                false,
             );
 
             // fund another account so we can send 2 good transactions in a single batch.
@@ -1146,6 +1159,7 @@ mod tests {
                     bank_forks,
                     &Arc::new(PrioritizationFeeCache::new(0u64)),
                     false,
+                    false,
                 );
 
                 // wait for banking_stage to eat the packets
@@ -1342,6 +1356,7 @@ mod tests {
                 bank_forks,
                 &Arc::new(PrioritizationFeeCache::new(0u64)),
                 false,
+                false,
             );
 
             let keypairs = (0..100).map(|_| Keypair::new()).collect_vec();
@@ -9,6 +9,7 @@ use {
     solana_sdk::{
         clock::Slot,
         feature_set::FeatureSet,
+        fee::FeeBudgetLimits,
         hash::Hash,
         message::{v0::LoadedAddresses, AddressLoaderError, Message, SimpleAddressLoader},
         pubkey::Pubkey,
@@ -191,6 +192,26 @@ impl Ord for ImmutableDeserializedPacket {
     }
 }
 
+/// Deserializes the transaction in `packet` and returns the prioritization fee,
+/// in lamports, declared by its compute-budget instructions.
+pub fn estimate_priority_fee_from_packet(
+    packet: &Packet,
+    feature_set: &FeatureSet,
+) -> Result<u64, DeserializedPacketError> {
+    let versioned_transaction: VersionedTransaction = packet.deserialize_slice(..)?;
+    let sanitized_transaction = SanitizedVersionedTransaction::try_from(versioned_transaction)?;
+    let compute_budget_limits = process_compute_budget_instructions(
+        sanitized_transaction
+            .get_message()
+            .program_instructions_iter()
+            .map(|(pubkey, ix)| (pubkey, SVMInstruction::from(ix))),
+        feature_set,
+    )
+    .map_err(|_| DeserializedPacketError::PrioritizationFailure)?;
+
+    Ok(FeeBudgetLimits::from(compute_budget_limits).prioritization_fee)
+}
+
 /// Read the transaction message from packet data
 fn packet_message(packet: &Packet) -> Result<&[u8], DeserializedPacketError> {
     let (sig_len, sig_size) = packet
@@ -260,4 +281,39 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_estimate_priority_fee_from_packet() {
+        let keypair = Keypair::new();
+        let ixs = vec![
+            system_instruction::transfer(&keypair.pubkey(), &Pubkey::new_unique(), 1),
+            compute_budget::ComputeBudgetInstruction::set_compute_unit_limit(200_000),
+            compute_budget::ComputeBudgetInstruction::set_compute_unit_price(5_000_000),
+        ];
+        let tx = Transaction::new_signed_with_payer(
+            &ixs,
+            Some(&keypair.pubkey()),
+            &[&keypair],
+            Hash::new_unique(),
+        );
+        let packet = Packet::from_data(None, tx).unwrap();
+        // 200_000 CUs at 5 lamports per CU
+        assert_eq!(
+            estimate_priority_fee_from_packet(&packet, &FeatureSet::all_enabled()).unwrap(),
+            1_000_000
+        );
+
+        // Without a price instruction there is no priority fee.
+        let tx =
+            system_transaction::transfer(&keypair, &Pubkey::new_unique(), 1, Hash::new_unique());
+        let packet = Packet::from_data(None, tx).unwrap();
+        assert_eq!(
+            estimate_priority_fee_from_packet(&packet, &FeatureSet::all_enabled()).unwrap(),
+            0
+        );
+
+        // Packets that fail to deserialize are rejected.
+        let packet = Packet::default();
+        assert!(estimate_priority_fee_from_packet(&packet, &FeatureSet::all_enabled()).is_err());
+    }
 }
@@ -12,33 +12,70 @@ use {
             ConsumeWork, FinishedConsumeWork, MaxAge, TransactionBatchId, TransactionId,
         },
         transaction_scheduler::{
-            transaction_priority_id::TransactionPriorityId, transaction_state::TransactionState,
-            transaction_state_container::StateContainer,
+            transaction_priority_id::TransactionPriorityId,
+            transaction_state::TransactionState,
+            transaction_state_container::{StateContainer, TransactionStateContainer},
         },
     },
     crossbeam_channel::{Receiver, Sender, TryRecvError},
     itertools::izip,
-    prio_graph::{AccessKind, GraphNode, PrioGraph},
+    prio_graph::{AccessKind, GraphNode, PrioGraph, TopLevelId},
     solana_cost_model::block_cost_limits::MAX_BLOCK_UNITS,
-    solana_measure::measure_us,
+    solana_measure::{meas_dur, measure_us},
     solana_runtime_transaction::transaction_with_meta::TransactionWithMeta,
-    solana_sdk::{pubkey::Pubkey, saturating_add_assign},
+    solana_sdk::{clock::Slot, pubkey::Pubkey, saturating_add_assign},
     solana_svm_transaction::svm_message::SVMMessage,
+    std::{
+        collections::HashMap,
+        hash::{Hash, Hasher},
+        time::{Duration, Instant},
+    },
 };
 
+/// Identifier of a transaction within the `PrioGraph`.
+/// Ordered by priority, then by `tie_breaker`, then by `priority_id`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct GraphTransactionId {
+    priority: u64,
+    tie_breaker: u64,
+    priority_id: TransactionPriorityId,
+}
+
+impl GraphTransactionId {
+    fn new(priority_id: TransactionPriorityId, priority: u64, tie_breaker: u64) -> Self {
+        Self {
+            priority,
+            tie_breaker,
+            priority_id,
+        }
+    }
+}
+
+impl Hash for GraphTransactionId {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.priority_id.hash(state)
+    }
+}
+
+impl TopLevelId<Self> for GraphTransactionId {
+    fn id(&self) -> Self {
+        *self
+    }
+}
+
 #[inline(always)]
 fn passthrough_priority(
-    id: &TransactionPriorityId,
-    _graph_node: &GraphNode<TransactionPriorityId>,
-) -> TransactionPriorityId {
+    id: &GraphTransactionId,
+    _graph_node: &GraphNode<GraphTransactionId>,
+) -> GraphTransactionId {
     *id
 }
 
 type SchedulerPrioGraph = PrioGraph<
-    TransactionPriorityId,
+    GraphTransactionId,
     Pubkey,
-    TransactionPriorityId,
-    fn(&TransactionPriorityId, &GraphNode<TransactionPriorityId>) -> TransactionPriorityId,
+    GraphTransactionId,
+    fn(&GraphTransactionId, &GraphNode<GraphTransactionId>) -> GraphTransactionId,
 >;
 
 pub(crate) struct PrioGraphSchedulerConfig {
@@ -46,6 +83,22 @@ pub(crate) struct PrioGraphSchedulerConfig {
     pub max_transactions_per_scheduling_pass: usize,
     pub look_ahead_window_size: usize,
     pub target_transactions_per_batch: usize,
+    /// If true, transactions of equal priority are scheduled in order of
+    /// increasing cost, favoring the number of transactions scheduled.
+    pub prefer_smaller_on_tie: bool,
+    /// If set, the given cost is held back from `max_scheduled_cus` for
+    /// transactions that reference the given account, as long as such a
+    /// transaction is waiting in the look-ahead window.
+    pub reserved_account: Option<(Pubkey, u64)>,
+    /// If set, lock checking is timed for every transaction so it can be
+    /// reported apart from batching in `SchedulingTimings`. Otherwise both
+    /// are reported as zero.
+    pub detailed_timings: bool,
+    /// If set, within a scheduling pass each transaction of a fee-payer has its
+    /// priority multiplied by this factor once for every higher-priority
+    /// transaction of the same fee-payer before it, so a payer's effective
+    /// priority decays as it takes more of the block. Must be within (0, 1].
+    pub payer_priority_decay: Option<f64>,
 }
 
 impl Default for PrioGraphSchedulerConfig {
@@ -55,6 +108,10 @@ impl Default for PrioGraphSchedulerConfig {
             max_transactions_per_scheduling_pass: 100_000,
             look_ahead_window_size: 2048,
             target_transactions_per_batch: TARGET_NUM_TRANSACTIONS_PER_BATCH,
+            prefer_smaller_on_tie: false,
+            reserved_account: None,
+            detailed_timings: false,
+            payer_priority_decay: None,
         }
     }
 }
@@ -66,15 +123,75 @@ pub(crate) struct PrioGraphScheduler<Tx> {
     finished_consume_work_receiver: Receiver<FinishedConsumeWork<Tx>>,
     prio_graph: SchedulerPrioGraph,
     config: PrioGraphSchedulerConfig,
+    metrics: PrioGraphSchedulerMetrics,
+}
+
+/// Scheduling counters accumulated since the last `report_metrics()`.
+#[derive(Debug, Default)]
+struct PrioGraphSchedulerMetrics {
+    num_scheduled: u64,
+    num_filtered_out: u64,
+    num_unschedulable: u64,
+    graph_build_us: u64,
+    lock_check_us: u64,
+    batching_us: u64,
+    send_us: u64,
+}
+
+impl PrioGraphSchedulerMetrics {
+    fn accumulate(&mut self, summary: &SchedulingSummary) {
+        saturating_add_assign!(self.num_scheduled, summary.num_scheduled as u64);
+        saturating_add_assign!(self.num_filtered_out, summary.num_filtered_out as u64);
+        saturating_add_assign!(self.num_unschedulable, summary.num_unschedulable as u64);
+        saturating_add_assign!(self.graph_build_us, summary.timings.graph_build_us);
+        saturating_add_assign!(self.lock_check_us, summary.timings.lock_check_us);
+        saturating_add_assign!(self.batching_us, summary.timings.batching_us);
+        saturating_add_assign!(self.send_us, summary.timings.send_us);
+    }
+
+    fn report_and_reset(&mut self, slot: Slot, num_in_flight: usize) {
+        let Self {
+            num_scheduled,
+            num_filtered_out,
+            num_unschedulable,
+            graph_build_us,
+            lock_check_us,
+            batching_us,
+            send_us,
+        } = std::mem::take(self);
+        datapoint_info!(
+            "banking_stage_prio_graph_scheduler",
+            ("slot", slot as i64, i64),
+            ("num_scheduled", num_scheduled as i64, i64),
+            ("num_filtered", num_filtered_out as i64, i64),
+            ("num_blocked", num_unschedulable as i64, i64),
+            ("in_flight", num_in_flight as i64, i64),
+            ("graph_build_us", graph_build_us as i64, i64),
+            ("lock_check_us", lock_check_us as i64, i64),
+            ("batching_us", batching_us as i64, i64),
+            ("send_us", send_us as i64, i64),
+        );
+    }
 }
 
 impl<Tx: TransactionWithMeta> PrioGraphScheduler<Tx> {
+    /// Creates a scheduler dispatching work to one worker thread per sender.
+    /// Panics if `consume_work_senders` is empty, as there would be no worker
+    /// to schedule transactions to, or if `config.payer_priority_decay` is not
+    /// within (0, 1].
     pub(crate) fn new(
         consume_work_senders: Vec<Sender<ConsumeWork<Tx>>>,
         finished_consume_work_receiver: Receiver<FinishedConsumeWork<Tx>>,
         config: PrioGraphSchedulerConfig,
     ) -> Self {
         let num_threads = consume_work_senders.len();
+        assert!(num_threads > 0, "consume work senders must not be empty");
+        if let Some(decay) = config.payer_priority_decay {
+            assert!(
+                decay > 0.0 && decay <= 1.0,
+                "payer priority decay must be within (0, 1]"
+            );
+        }
         Self {
             in_flight_tracker: InFlightTracker::new(num_threads),
             account_locks: ThreadAwareAccountLocks::new(num_threads),
@@ -82,6 +199,7 @@ impl<Tx: TransactionWithMeta> PrioGraphScheduler<Tx> {
             finished_consume_work_receiver,
             prio_graph: PrioGraph::new(passthrough_priority),
             config,
+            metrics: PrioGraphSchedulerMetrics::default(),
         }
     }
 
@@ -122,6 +240,10 @@ impl<Tx: TransactionWithMeta> PrioGraphScheduler<Tx> {
                 num_unschedulable: 0,
                 num_filtered_out: 0,
                 filter_time_us: 0,
+                avg_scheduling_latency_us: 0,
+                blocking_account_counts: HashMap::new(),
+                max_search_depth: 0,
+                timings: SchedulingTimings::default(),
             });
         }
 
@@ -136,11 +258,44 @@ impl<Tx: TransactionWithMeta> PrioGraphScheduler<Tx> {
         // Track metrics on filter.
         let mut num_filtered_out: usize = 0;
         let mut total_filter_time_us: u64 = 0;
-
+        let mut total_scheduling_latency_us: u64 = 0;
+
+        // Track how many times each account blocked a transaction's write lock.
+        let mut blocking_account_counts = HashMap::new();
+
+        // Track how many transactions were examined to find each scheduled transaction.
+        let mut search_depth: usize = 0;
+        let mut max_search_depth: usize = 0;
+
+        // Track time spent in each phase, accumulated at full precision.
+        // Lock checking and batching are interleaved per transaction, so
+        // only the scheduling loop as a whole is timed unless detailed
+        // timings are requested.
+        let detailed_timings = self.config.detailed_timings;
+        let mut graph_build_time = Duration::ZERO;
+        let mut lock_check_time = Duration::ZERO;
+        let mut schedule_loop_time = Duration::ZERO;
+        let mut schedule_loop_send_time = Duration::ZERO;
+        let mut send_time = Duration::ZERO;
+
+        let prefer_smaller_on_tie = self.config.prefer_smaller_on_tie;
+        let payer_priority_decay = self.config.payer_priority_decay;
+        // Number of transactions of each fee-payer inserted into the prio-graph in this pass.
+        let mut payer_transaction_counts: HashMap<Pubkey, i32> = HashMap::new();
+        let reserved_account = self.config.reserved_account.map(|(account, _)| account);
+        // Running total of CUs in flight or scheduled in this pass, and number of transactions
+        // referencing the reserved account waiting in the prio-graph.
+        let mut scheduled_cus: u64 = self
+            .in_flight_tracker
+            .cus_in_flight_per_thread()
+            .iter()
+            .sum();
+        let mut num_pending_reserved: usize = 0;
         let mut window_budget = self.config.look_ahead_window_size;
         let mut chunked_pops = |container: &mut S,
                                 prio_graph: &mut PrioGraph<_, _, _, _>,
-                                window_budget: &mut usize| {
+                                window_budget: &mut usize,
+                                num_pending_reserved: &mut usize| {
             while *window_budget > 0 {
                 const MAX_FILTER_CHUNK_SIZE: usize = 128;
                 let mut filter_array = [true; MAX_FILTER_CHUNK_SIZE];
@@ -168,9 +323,36 @@ impl<Tx: TransactionWithMeta> PrioGraphScheduler<Tx> {
 
                 for (id, filter_result) in ids.iter().zip(&filter_array[..chunk_size]) {
                     if *filter_result {
+                        let tie_breaker = if prefer_smaller_on_tie {
+                            container
+                                .get_transaction_state(id.id)
+                                .map_or(0, |state| u64::MAX - state.cost())
+                        } else {
+                            0
+                        };
                         let transaction = container.get_transaction_ttl(id.id).unwrap();
+                        let priority = match payer_priority_decay {
+                            Some(decay) => {
+                                let count = payer_transaction_counts
+                                    .entry(*transaction.transaction.fee_payer())
+                                    .or_default();
+                                let priority = (id.priority as f64 * decay.powi(*count)) as u64;
+                                *count += 1;
+                                priority
+                            }
+                            None => id.priority,
+                        };
+                        if reserved_account.is_some_and(|reserved_account| {
+                            transaction
+                                .transaction
+                                .account_keys()
+                                .iter()
+                                .any(|key| *key == reserved_account)
+                        }) {
+                            saturating_add_assign!(*num_pending_reserved, 1);
+                        }
                         prio_graph.insert_transaction(
-                            *id,
+                            GraphTransactionId::new(*id, priority, tie_breaker),
                             Self::get_transaction_account_access(transaction),
                         );
                     } else {
@@ -187,7 +369,13 @@ impl<Tx: TransactionWithMeta> PrioGraphScheduler<Tx> {
 
         // Create the initial look-ahead window.
         // Check transactions against filter, remove from container if it fails.
-        chunked_pops(container, &mut self.prio_graph, &mut window_budget);
+        let (_, chunked_pops_time) = meas_dur!(chunked_pops(
+            container,
+            &mut self.prio_graph,
+            &mut window_budget,
+            &mut num_pending_reserved
+        ));
+        graph_build_time += chunked_pops_time;
 
         let mut unblock_this_batch = Vec::with_capacity(
             self.consume_work_senders.len() * self.config.target_transactions_per_batch,
@@ -201,8 +389,11 @@ impl<Tx: TransactionWithMeta> PrioGraphScheduler<Tx> {
                 break;
             }
 
-            while let Some(id) = self.prio_graph.pop() {
-                unblock_this_batch.push(id);
+            let schedule_loop_start = Instant::now();
+            while let Some(graph_id) = self.prio_graph.pop() {
+                unblock_this_batch.push(graph_id);
+                let id = graph_id.priority_id;
+                saturating_add_assign!(search_depth, 1);
 
                 // Should always be in the container, during initial testing phase panic.
                 // Later, we can replace with a continue in case this does happen.
@@ -210,6 +401,31 @@ impl<Tx: TransactionWithMeta> PrioGraphScheduler<Tx> {
                     panic!("transaction state must exist")
                 };
 
+                // Transactions not referencing the reserved account cannot use the
+                // reserved portion of the budget, while a transaction referencing it
+                // is waiting.
+                if let Some((reserved_account, reserved_cost)) = &self.config.reserved_account {
+                    let transaction = &transaction_state.transaction_ttl().transaction;
+                    if transaction
+                        .account_keys()
+                        .iter()
+                        .any(|key| key == reserved_account)
+                    {
+                        // Leaves the prio-graph, whether it is scheduled or not.
+                        num_pending_reserved = num_pending_reserved.saturating_sub(1);
+                    } else if num_pending_reserved > 0
+                        && scheduled_cus.saturating_add(transaction_state.cost())
+                            > self.config.max_scheduled_cus.saturating_sub(*reserved_cost)
+                    {
+                        blocking_locks.take_locks(transaction);
+                        unschedulable_ids.push(id);
+                        saturating_add_assign!(num_unschedulable, 1);
+                        continue;
+                    }
+                }
+
+                let enqueue_time = transaction_state.enqueue_time();
+                let lock_check_start = detailed_timings.then(Instant::now);
                 let maybe_schedule_info = try_schedule_transaction(
                     transaction_state,
                     &pre_lock_filter,
@@ -226,6 +442,9 @@ impl<Tx: TransactionWithMeta> PrioGraphScheduler<Tx> {
                         )
                     },
                 );
+                if let Some(lock_check_start) = lock_check_start {
+                    lock_check_time += lock_check_start.elapsed();
+                }
 
                 match maybe_schedule_info {
                     Err(TransactionSchedulingError::Filtered) => {
@@ -235,6 +454,18 @@ impl<Tx: TransactionWithMeta> PrioGraphScheduler<Tx> {
                         unschedulable_ids.push(id);
                         saturating_add_assign!(num_unschedulable, 1);
                     }
+                    Err(TransactionSchedulingError::UnschedulableAccountLocks) => {
+                        let transaction = &transaction_state.transaction_ttl().transaction;
+                        for (index, account) in transaction.account_keys().iter().enumerate() {
+                            if transaction.is_writable(index)
+                                && self.account_locks.is_locked(account)
+                            {
+                                *blocking_account_counts.entry(*account).or_insert(0) += 1;
+                            }
+                        }
+                        unschedulable_ids.push(id);
+                        saturating_add_assign!(num_unschedulable, 1);
+                    }
                     Ok(TransactionSchedulingInfo {
                         thread_id,
                         transaction,
@@ -242,6 +473,13 @@ impl<Tx: TransactionWithMeta> PrioGraphScheduler<Tx> {
                         cost,
                     }) => {
                         saturating_add_assign!(num_scheduled, 1);
+                        saturating_add_assign!(scheduled_cus, cost);
+                        max_search_depth = max_search_depth.max(search_depth);
+                        search_depth = 0;
+                        saturating_add_assign!(
+                            total_scheduling_latency_us,
+                            enqueue_time.elapsed().as_micros() as u64
+                        );
                         batches.transactions[thread_id].push(transaction);
                         batches.ids[thread_id].push(id.id);
                         batches.max_ages[thread_id].push(max_age);
@@ -250,10 +488,11 @@ impl<Tx: TransactionWithMeta> PrioGraphScheduler<Tx> {
                         // If target batch size is reached, send only this batch.
                         if batches.ids[thread_id].len() >= self.config.target_transactions_per_batch
                         {
-                            saturating_add_assign!(
-                                num_sent,
-                                self.send_batch(&mut batches, thread_id)?
-                            );
+                            let (send_result, batch_send_time) =
+                                meas_dur!(self.send_batch(&mut batches, thread_id));
+                            send_time += batch_send_time;
+                            schedule_loop_send_time += batch_send_time;
+                            saturating_add_assign!(num_sent, send_result?);
                         }
 
                         // if the thread is at max_cu_per_thread, remove it from the schedulable threads
@@ -274,13 +513,22 @@ impl<Tx: TransactionWithMeta> PrioGraphScheduler<Tx> {
                     }
                 }
             }
+            schedule_loop_time += schedule_loop_start.elapsed();
 
             // Send all non-empty batches
-            saturating_add_assign!(num_sent, self.send_batches(&mut batches)?);
+            let (send_result, batches_send_time) = meas_dur!(self.send_batches(&mut batches));
+            send_time += batches_send_time;
+            saturating_add_assign!(num_sent, send_result?);
 
             // Refresh window budget and do chunked pops
             saturating_add_assign!(window_budget, unblock_this_batch.len());
-            chunked_pops(container, &mut self.prio_graph, &mut window_budget);
+            let (_, chunked_pops_time) = meas_dur!(chunked_pops(
+                container,
+                &mut self.prio_graph,
+                &mut window_budget,
+                &mut num_pending_reserved
+            ));
+            graph_build_time += chunked_pops_time;
 
             // Unblock all transactions that were blocked by the transactions that were just sent.
             for id in unblock_this_batch.drain(..) {
@@ -289,7 +537,9 @@ impl<Tx: TransactionWithMeta> PrioGraphScheduler<Tx> {
         }
 
         // Send batches for any remaining transactions
-        saturating_add_assign!(num_sent, self.send_batches(&mut batches)?);
+        let (send_result, batches_send_time) = meas_dur!(self.send_batches(&mut batches));
+        send_time += batches_send_time;
+        saturating_add_assign!(num_sent, send_result?);
 
         // Push unschedulable ids back into the container
         for id in unschedulable_ids {
@@ -297,8 +547,8 @@ impl<Tx: TransactionWithMeta> PrioGraphScheduler<Tx> {
         }
 
         // Push remaining transactions back into the container
-        while let Some((id, _)) = self.prio_graph.pop_and_unblock() {
-            container.push_id_into_queue(id);
+        while let Some((graph_id, _)) = self.prio_graph.pop_and_unblock() {
+            container.push_id_into_queue(graph_id.priority_id);
         }
         // No more remaining items in the queue.
         // Clear here to make sure the next scheduling pass starts fresh
@@ -310,12 +560,44 @@ impl<Tx: TransactionWithMeta> PrioGraphScheduler<Tx> {
             "number of scheduled and sent transactions must match"
         );
 
-        Ok(SchedulingSummary {
+        let scheduling_summary = SchedulingSummary {
             num_scheduled,
             num_unschedulable,
             num_filtered_out,
             filter_time_us: total_filter_time_us,
-        })
+            avg_scheduling_latency_us: total_scheduling_latency_us
+                .checked_div(num_scheduled as u64)
+                .unwrap_or_default(),
+            blocking_account_counts,
+            max_search_depth,
+            timings: SchedulingTimings {
+                graph_build_us: graph_build_time.as_micros() as u64,
+                lock_check_us: lock_check_time.as_micros() as u64,
+                batching_us: if detailed_timings {
+                    schedule_loop_time
+                        .saturating_sub(schedule_loop_send_time)
+                        .saturating_sub(lock_check_time)
+                        .as_micros() as u64
+                } else {
+                    0
+                },
+                send_us: send_time.as_micros() as u64,
+            },
+        };
+        self.metrics.accumulate(&scheduling_summary);
+        Ok(scheduling_summary)
+    }
+
+    /// Reports scheduling counters and timings accumulated since the last
+    /// report for `slot`, along with the number of in-flight transactions,
+    /// then resets the accumulated values.
+    pub(crate) fn report_metrics(&mut self, slot: Slot) {
+        let num_in_flight = self
+            .in_flight_tracker
+            .num_in_flight_per_thread()
+            .iter()
+            .sum();
+        self.metrics.report_and_reset(slot, num_in_flight);
     }
 
     /// Receive completed batches of transactions without blocking.
@@ -479,6 +761,24 @@ impl<Tx: TransactionWithMeta> PrioGraphScheduler<Tx> {
             .unwrap()
     }
 
+    /// Estimates how many of the queued transactions could be executed
+    /// simultaneously without write-conflicts. Transactions are visited in
+    /// priority order and greedily accepted if they do not conflict with any
+    /// previously accepted transaction, so the result is a lower bound on
+    /// the size of the largest non-conflicting set.
+    pub(crate) fn estimated_parallelism(&self, container: &TransactionStateContainer<Tx>) -> usize {
+        let mut account_set = ReadWriteAccountSet::default();
+        container
+            .queued_ids_by_priority()
+            .into_iter()
+            .filter_map(|priority_id| container.get_transaction_ttl(priority_id.id))
+            .filter(|transaction_ttl| {
+                let transaction = &transaction_ttl.transaction;
+                account_set.check_locks(transaction) && account_set.take_locks(transaction)
+            })
+            .count()
+    }
+
     /// Gets accessed accounts (resources) for use in `PrioGraph`.
     fn get_transaction_account_access(
         transaction: &SanitizedTransactionTTL<impl SVMMessage>,
@@ -509,6 +809,35 @@ pub(crate) struct SchedulingSummary {
     pub num_filtered_out: usize,
     /// Time spent filtering transactions
     pub filter_time_us: u64,
+    /// Average time scheduled transactions spent in the container before
+    /// being scheduled.
+    pub avg_scheduling_latency_us: u64,
+    /// Number of times each account, already locked by scheduled transactions,
+    /// blocked a transaction requesting it for write from being locked.
+    pub blocking_account_counts: HashMap<Pubkey, usize>,
+    /// Highest number of transactions examined, counting from the previously
+    /// scheduled one, to find a transaction to schedule. High values indicate
+    /// contention forcing the scheduler to scan deep.
+    pub max_search_depth: usize,
+    /// Time spent in each phase of scheduling.
+    pub timings: SchedulingTimings,
+}
+
+/// Breakdown of time spent in a scheduling pass.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct SchedulingTimings {
+    /// Time spent popping transactions from the container, filtering them,
+    /// and inserting them into the prio-graph.
+    pub graph_build_us: u64,
+    /// Time spent checking and taking account locks. Only measured with
+    /// `PrioGraphSchedulerConfig::detailed_timings`.
+    pub lock_check_us: u64,
+    /// Time spent in the scheduling loop outside of lock checking and
+    /// sending, mostly adding scheduled transactions to batches. Only
+    /// measured with `PrioGraphSchedulerConfig::detailed_timings`.
+    pub batching_us: u64,
+    /// Time spent sending batches to workers.
+    pub send_us: u64,
 }
 
 struct Batches<Tx> {
@@ -566,9 +895,11 @@ struct TransactionSchedulingInfo<Tx> {
 enum TransactionSchedulingError {
     /// Transaction was filtered out before locking.
     Filtered,
-    /// Transaction cannot be scheduled due to conflicts, or
-    /// higher priority conflicting transactions are unschedulable.
+    /// Higher priority conflicting transactions are unschedulable.
     UnschedulableConflicts,
+    /// Transaction cannot be scheduled because its accounts are locked on
+    /// conflicting threads.
+    UnschedulableAccountLocks,
 }
 
 fn try_schedule_transaction<Tx: TransactionWithMeta>(
@@ -608,7 +939,7 @@ fn try_schedule_transaction<Tx: TransactionWithMeta>(
         thread_selector,
     ) else {
         blocking_locks.take_locks(transaction);
-        return Err(TransactionSchedulingError::UnschedulableConflicts);
+        return Err(TransactionSchedulingError::UnschedulableAccountLocks);
     };
 
     let sanitized_transaction_ttl = transaction_state.transition_to_pending();
@@ -756,6 +1087,27 @@ mod tests {
         true
     }
 
+    #[test]
+    #[should_panic(expected = "consume work senders must not be empty")]
+    fn test_new_without_workers() {
+        create_test_frame(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "payer priority decay must be within (0, 1]")]
+    fn test_new_with_invalid_payer_priority_decay() {
+        let (consume_work_sender, _work_receiver) = unbounded();
+        let (_finished_work_sender, finished_consume_work_receiver) = unbounded();
+        PrioGraphScheduler::<RuntimeTransaction<SanitizedTransaction>>::new(
+            vec![consume_work_sender],
+            finished_consume_work_receiver,
+            PrioGraphSchedulerConfig {
+                payer_priority_decay: Some(1.5),
+                ..PrioGraphSchedulerConfig::default()
+            },
+        );
+    }
+
     #[test]
     fn test_schedule_disconnected_channel() {
         let (mut scheduler, work_receivers, _finished_work_sender) = create_test_frame(1);
@@ -784,6 +1136,289 @@ mod tests {
         assert_eq!(collect_work(&work_receivers[0]).1, vec![vec![1, 0]]);
     }
 
+    #[test]
+    fn test_schedule_prefer_smaller_on_tie() {
+        for (prefer_smaller_on_tie, expected_ids) in [(false, vec![1, 0]), (true, vec![0, 1])] {
+            let (consume_work_sender, work_receiver) = unbounded();
+            let (_finished_work_sender, finished_consume_work_receiver) = unbounded();
+            let mut scheduler = PrioGraphScheduler::new(
+                vec![consume_work_sender],
+                finished_consume_work_receiver,
+                PrioGraphSchedulerConfig {
+                    prefer_smaller_on_tie,
+                    ..PrioGraphSchedulerConfig::default()
+                },
+            );
+
+            // Equal priority, non-conflicting. The first inserted is smaller.
+            let mut container = TransactionStateContainer::with_capacity(16);
+            for cost in [1_000, 10_000] {
+                let transaction =
+                    prioritized_tranfers(&Keypair::new(), [Pubkey::new_unique()], 1, 1);
+                let packet = Arc::new(
+                    ImmutableDeserializedPacket::new(
+                        Packet::from_data(None, transaction.to_versioned_transaction()).unwrap(),
+                    )
+                    .unwrap(),
+                );
+                let transaction_ttl = SanitizedTransactionTTL {
+                    transaction,
+                    max_age: MaxAge::MAX,
+                };
+                container.insert_new_transaction(transaction_ttl, packet, 1, cost);
+            }
+
+            let scheduling_summary = scheduler
+                .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+                .unwrap();
+            assert_eq!(scheduling_summary.num_scheduled, 2);
+            assert_eq!(collect_work(&work_receiver).1, vec![expected_ids]);
+        }
+    }
+
+    #[test]
+    fn test_schedule_reserved_account() {
+        const TEST_TRANSACTION_COST: u64 = 5000;
+        let reserved_account = Pubkey::new_unique();
+        let (consume_work_sender, work_receiver) = unbounded();
+        let (_finished_work_sender, finished_consume_work_receiver) = unbounded();
+        let mut scheduler = PrioGraphScheduler::new(
+            vec![consume_work_sender],
+            finished_consume_work_receiver,
+            PrioGraphSchedulerConfig {
+                max_scheduled_cus: 3 * TEST_TRANSACTION_COST,
+                reserved_account: Some((reserved_account, TEST_TRANSACTION_COST)),
+                ..PrioGraphSchedulerConfig::default()
+            },
+        );
+
+        // Four high-priority transactions, followed by a low-priority one
+        // touching the reserved account.
+        let mut container = create_container(
+            (0..4)
+                .map(|i| (Keypair::new(), Pubkey::new_unique(), 10 + i))
+                .chain(std::iter::once((Keypair::new(), reserved_account, 1)))
+                .map(|(keypair, to_pubkey, priority)| (keypair, [to_pubkey], 1, priority)),
+        );
+
+        // Only two high-priority transactions fit in the unreserved budget,
+        // the reserved budget goes to the low-priority transaction.
+        let scheduling_summary = scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 3);
+        assert_eq!(scheduling_summary.num_unschedulable, 2);
+        assert_eq!(collect_work(&work_receiver).1, vec![vec![3, 2, 4]]);
+
+        // Cost-limited transactions remain in the container.
+        assert_eq!(container.pop().map(|id| id.id), Some(1));
+        assert_eq!(container.pop().map(|id| id.id), Some(0));
+        assert!(container.pop().is_none());
+    }
+
+    #[test]
+    fn test_schedule_reserved_account_released() {
+        const TEST_TRANSACTION_COST: u64 = 5000;
+        let (consume_work_sender, work_receiver) = unbounded();
+        let (_finished_work_sender, finished_consume_work_receiver) = unbounded();
+        let mut scheduler = PrioGraphScheduler::new(
+            vec![consume_work_sender],
+            finished_consume_work_receiver,
+            PrioGraphSchedulerConfig {
+                max_scheduled_cus: 3 * TEST_TRANSACTION_COST,
+                reserved_account: Some((Pubkey::new_unique(), TEST_TRANSACTION_COST)),
+                ..PrioGraphSchedulerConfig::default()
+            },
+        );
+
+        // No transaction touches the reserved account, so the whole budget is
+        // available.
+        let mut container =
+            create_container((0..4).map(|i| (Keypair::new(), [Pubkey::new_unique()], 1, 10 + i)));
+        let scheduling_summary = scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 3);
+        assert_eq!(scheduling_summary.num_unschedulable, 0);
+        assert_eq!(collect_work(&work_receiver).1, vec![vec![3, 2, 1]]);
+    }
+
+    #[test]
+    fn test_schedule_payer_priority_decay() {
+        let payer = Keypair::new();
+        let other_payer = Keypair::new();
+
+        for (payer_priority_decay, expected_ids) in [
+            (None, vec![vec![0], vec![1, 2]]),
+            (Some(0.5), vec![vec![0], vec![2, 1]]),
+        ] {
+            let (consume_work_sender, work_receiver) = unbounded();
+            let (_finished_work_sender, finished_consume_work_receiver) = unbounded();
+            let mut scheduler = PrioGraphScheduler::new(
+                vec![consume_work_sender],
+                finished_consume_work_receiver,
+                PrioGraphSchedulerConfig {
+                    look_ahead_window_size: 2,
+                    payer_priority_decay,
+                    ..PrioGraphSchedulerConfig::default()
+                },
+            );
+            // The payer's second transaction outranks the other payer's, until
+            // its priority decays because the payer's first one is scheduled.
+            let mut container = create_container([
+                (&payer, [Pubkey::new_unique()], 1, 100),
+                (&payer, [Pubkey::new_unique()], 1, 90),
+                (&other_payer, [Pubkey::new_unique()], 1, 60),
+            ]);
+
+            let scheduling_summary = scheduler
+                .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+                .unwrap();
+            assert_eq!(scheduling_summary.num_scheduled, 3);
+            assert_eq!(collect_work(&work_receiver).1, expected_ids);
+        }
+    }
+
+    #[test]
+    fn test_schedule_latency() {
+        let (mut scheduler, _work_receivers, _finished_work_sender) = create_test_frame(1);
+        let mut container = create_container([(&Keypair::new(), &[Pubkey::new_unique()], 1, 1)]);
+
+        std::thread::sleep(std::time::Duration::from_millis(1));
+        let scheduling_summary = scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 1);
+        assert!(scheduling_summary.avg_scheduling_latency_us >= 1000);
+
+        // nothing scheduled, no latency
+        let scheduling_summary = scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 0);
+        assert_eq!(scheduling_summary.avg_scheduling_latency_us, 0);
+    }
+
+    #[test]
+    fn test_schedule_blocking_account_counts() {
+        let (mut scheduler, _work_receivers, _finished_work_sender) = create_test_frame(2);
+        scheduler.config.look_ahead_window_size = 2;
+
+        // Same setup as `test_schedule_priority_guard`: [4] cannot lock its
+        // writable accounts [1] and [2], held by [0] and [1] on different
+        // threads. [5] is only blocked by the unschedulable [4], not counted.
+        let accounts = (0..8).map(|_| Keypair::new()).collect_vec();
+        let mut container = create_container([
+            (&accounts[0], &[accounts[1].pubkey()], 1, 6),
+            (&accounts[2], &[accounts[3].pubkey()], 1, 5),
+            (&accounts[4], &[accounts[5].pubkey()], 1, 4),
+            (&accounts[6], &[accounts[7].pubkey()], 1, 3),
+            (&accounts[1], &[accounts[2].pubkey()], 1, 2),
+            (&accounts[2], &[accounts[3].pubkey()], 1, 1),
+        ]);
+
+        let scheduling_summary = scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 4);
+        assert_eq!(scheduling_summary.num_unschedulable, 2);
+        assert_eq!(
+            scheduling_summary.blocking_account_counts,
+            HashMap::from([(accounts[1].pubkey(), 1), (accounts[2].pubkey(), 1)])
+        );
+    }
+
+    #[test]
+    fn test_schedule_max_search_depth() {
+        let (mut scheduler, _work_receivers, _finished_work_sender) = create_test_frame(2);
+
+        // Schedule two non-conflicting transactions onto different threads, their locks are
+        // held until the work is completed.
+        let accounts = (0..4).map(|_| Keypair::new()).collect_vec();
+        let mut container = create_container([
+            (&accounts[0], &[accounts[1].pubkey()], 1, 2),
+            (&accounts[2], &[accounts[3].pubkey()], 1, 1),
+        ]);
+        let scheduling_summary = scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 2);
+        assert_eq!(scheduling_summary.max_search_depth, 1);
+
+        // The two highest priority transactions each conflict with both threads, only the
+        // third one examined can be scheduled.
+        let mut container = create_container([
+            (&accounts[1], vec![accounts[2].pubkey()], 1, 3),
+            (&accounts[0], vec![accounts[3].pubkey()], 1, 2),
+            (&Keypair::new(), vec![Pubkey::new_unique()], 1, 1),
+        ]);
+        let scheduling_summary = scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 1);
+        assert_eq!(scheduling_summary.num_unschedulable, 2);
+        assert_eq!(scheduling_summary.max_search_depth, 3);
+    }
+
+    #[test]
+    fn test_schedule_timings() {
+        let (mut scheduler, work_receivers, _finished_work_sender) = create_test_frame(2);
+        scheduler.config.detailed_timings = true;
+        let mut container =
+            create_container((0..2_000).map(|i| (Keypair::new(), [Pubkey::new_unique()], 1, i)));
+
+        let scheduling_summary = scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 2_000);
+        assert_eq!(
+            work_receivers
+                .iter()
+                .map(|work_receiver| collect_work(work_receiver).1.concat().len())
+                .sum::<usize>(),
+            2_000
+        );
+
+        let SchedulingTimings {
+            graph_build_us,
+            lock_check_us,
+            batching_us,
+            send_us,
+        } = scheduling_summary.timings;
+        assert!(graph_build_us > 0);
+        assert!(lock_check_us > 0);
+        assert!(batching_us > 0);
+        assert!(send_us > 0);
+    }
+
+    #[test]
+    fn test_report_metrics() {
+        let (mut scheduler, _work_receivers, _finished_work_sender) = create_test_frame(1);
+        let mut container =
+            create_container((0..4).map(|i| (Keypair::new(), [Pubkey::new_unique()], 1, i)));
+        scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        let mut container =
+            create_container((0..2).map(|i| (Keypair::new(), [Pubkey::new_unique()], 1, i)));
+        scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+
+        let metrics = &scheduler.metrics;
+        assert_eq!(metrics.num_scheduled, 6);
+        assert_eq!(metrics.num_filtered_out, 0);
+        assert_eq!(metrics.num_unschedulable, 0);
+
+        scheduler.report_metrics(1);
+        let metrics = &scheduler.metrics;
+        assert_eq!(metrics.num_scheduled, 0);
+        assert_eq!(metrics.graph_build_us, 0);
+        assert_eq!(metrics.lock_check_us, 0);
+        assert_eq!(metrics.batching_us, 0);
+        assert_eq!(metrics.send_us, 0);
+    }
+
     #[test]
     fn test_schedule_single_threaded_conflict() {
         let (mut scheduler, work_receivers, _finished_work_sender) = create_test_frame(1);
@@ -928,4 +1563,69 @@ mod tests {
         assert_eq!(scheduling_summary.num_unschedulable, 0);
         assert_eq!(collect_work(&work_receivers[0]).1, vec![vec![2], vec![0]]);
     }
+
+    #[test]
+    fn test_estimated_parallelism() {
+        let (scheduler, _work_receivers, _finished_work_sender) = create_test_frame(1);
+
+        // All transactions write to the same fee-payer, so only one can run.
+        let keypair = Keypair::new();
+        let container = create_container((0..4).map(|i| (&keypair, [Pubkey::new_unique()], 1, i)));
+        assert_eq!(scheduler.estimated_parallelism(&container), 1);
+
+        // Distinct fee-payers and recipients never conflict.
+        let container =
+            create_container((0..4).map(|i| (Keypair::new(), [Pubkey::new_unique()], 1, i)));
+        assert_eq!(scheduler.estimated_parallelism(&container), 4);
+
+        let container = create_container(std::iter::empty::<(Keypair, [Pubkey; 1], u64, u64)>());
+        assert_eq!(scheduler.estimated_parallelism(&container), 0);
+    }
+
+    #[test]
+    fn test_schedule_tracer_transaction_scheduled_exactly_once() {
+        const NUM_CONTENDING: usize = 32;
+        let (mut scheduler, work_receivers, finished_work_sender) = create_test_frame(2);
+
+        // All transactions but the tracer write-lock the same fee-payer. The
+        // tracer has the lowest priority and does not contend with anything.
+        let contending_keypair = Keypair::new();
+        let tracer_keypair = Keypair::new();
+        let mut container = create_container(
+            (0..NUM_CONTENDING)
+                .map(|i| (&contending_keypair, 100 + i as u64))
+                .chain(std::iter::once((&tracer_keypair, 1)))
+                .map(|(keypair, priority)| (keypair, [Pubkey::new_unique()], 1, priority)),
+        );
+        let tracer_id = NUM_CONTENDING;
+
+        let mut scheduled_ids = vec![];
+        for _ in 0..=NUM_CONTENDING {
+            if container.is_empty() {
+                break;
+            }
+            scheduler
+                .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+                .unwrap();
+            for work_receiver in &work_receivers {
+                for work in work_receiver.try_iter() {
+                    scheduled_ids.extend_from_slice(&work.ids);
+                    finished_work_sender
+                        .send(FinishedConsumeWork {
+                            work,
+                            retryable_indexes: vec![],
+                        })
+                        .unwrap();
+                }
+            }
+            scheduler.receive_completed(&mut container).unwrap();
+        }
+
+        assert!(container.is_empty());
+        assert_eq!(scheduled_ids.len(), NUM_CONTENDING + 1);
+        assert_eq!(
+            scheduled_ids.iter().filter(|id| **id == tracer_id).count(),
+            1
+        );
+    }
 }
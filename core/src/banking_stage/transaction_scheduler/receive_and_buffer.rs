@@ -52,6 +52,9 @@ pub(crate) struct SanitizedTransactionReceiveAndBuffer {
     bank_forks: Arc<RwLock<BankForks>>,
 
     forwarding_enabled: bool,
+    /// Prioritize transactions by their total prioritization fee instead of
+    /// reward per unit of cost.
+    order_by_total_priority_fee: bool,
 }
 
 impl ReceiveAndBuffer for SanitizedTransactionReceiveAndBuffer {
@@ -135,11 +138,13 @@ impl SanitizedTransactionReceiveAndBuffer {
         packet_receiver: PacketDeserializer,
         bank_forks: Arc<RwLock<BankForks>>,
         forwarding_enabled: bool,
+        order_by_total_priority_fee: bool,
     ) -> Self {
         Self {
             packet_receiver,
             bank_forks,
             forwarding_enabled,
+            order_by_total_priority_fee,
         }
     }
 
@@ -235,8 +240,12 @@ impl SanitizedTransactionReceiveAndBuffer {
             {
                 saturating_add_assign!(post_transaction_check_count, 1);
 
-                let (priority, cost) =
-                    calculate_priority_and_cost(&transaction, &fee_budget_limits, &working_bank);
+                let (priority, cost) = calculate_priority_and_cost(
+                    &transaction,
+                    &fee_budget_limits,
+                    &working_bank,
+                    self.order_by_total_priority_fee,
+                );
                 let transaction_ttl = SanitizedTransactionTTL {
                     transaction,
                     max_age,
@@ -296,12 +305,19 @@ impl SanitizedTransactionReceiveAndBuffer {
 /// from user input. They should never be zero.
 /// Any difference in the prioritization is negligible for
 /// the current transaction costs.
+///
+/// If `order_by_total_priority_fee` is set, the priority is instead the
+/// transaction's total prioritization fee, regardless of its cost.
 fn calculate_priority_and_cost(
     transaction: &RuntimeTransaction<SanitizedTransaction>,
     fee_budget_limits: &FeeBudgetLimits,
     bank: &Bank,
+    order_by_total_priority_fee: bool,
 ) -> (u64, u64) {
     let cost = CostModel::calculate_cost(transaction, &bank.feature_set).sum();
+    if order_by_total_priority_fee {
+        return (fee_budget_limits.prioritization_fee, cost);
+    }
     let reward = bank.calculate_reward_for_transaction(transaction, fee_budget_limits);
 
     // We need a multiplier here to avoid rounding down too aggressively.
@@ -347,7 +363,16 @@ fn calculate_max_age(
 
 #[cfg(test)]
 mod tests {
-    use super::*;
+    use {
+        super::*,
+        crate::banking_stage::tests::create_slow_genesis_config,
+        solana_ledger::genesis_utils::GenesisConfigInfo,
+        solana_sdk::{
+            compute_budget::ComputeBudgetInstruction, fee_calculator::FeeRateGovernor,
+            message::Message, pubkey::Pubkey, signature::Keypair, signer::Signer,
+            system_instruction, transaction::Transaction,
+        },
+    };
 
     #[test]
     fn test_calculate_max_age() {
@@ -373,4 +398,58 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_calculate_priority_and_cost_order_by_total_priority_fee() {
+        let GenesisConfigInfo {
+            mut genesis_config, ..
+        } = create_slow_genesis_config(u64::MAX);
+        genesis_config.fee_rate_governor = FeeRateGovernor::new(5000, 0);
+        let bank = Bank::new_for_tests(&genesis_config);
+        let prioritized_transfer = |compute_unit_limit, compute_unit_price| {
+            let payer = Keypair::new();
+            let transaction = RuntimeTransaction::from_transaction_for_tests(
+                Transaction::new_unsigned(Message::new(
+                    &[
+                        system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1),
+                        ComputeBudgetInstruction::set_compute_unit_limit(compute_unit_limit),
+                        ComputeBudgetInstruction::set_compute_unit_price(compute_unit_price),
+                    ],
+                    Some(&payer.pubkey()),
+                )),
+            );
+            let fee_budget_limits = FeeBudgetLimits::from(
+                transaction
+                    .compute_budget_instruction_details()
+                    .sanitize_and_convert_to_compute_budget_limits(&bank.feature_set)
+                    .unwrap(),
+            );
+            (transaction, fee_budget_limits)
+        };
+
+        // a small and a large transaction at the same compute unit price, paying 10 and 2_000
+        // lamports priority fee respectively
+        let (small_tx, small_fee_budget_limits) = prioritized_transfer(1_000, 10_000);
+        let (large_tx, large_fee_budget_limits) = prioritized_transfer(200_000, 10_000);
+
+        let priority = |transaction, fee_budget_limits, order_by_total_priority_fee| {
+            calculate_priority_and_cost(
+                transaction,
+                fee_budget_limits,
+                &bank,
+                order_by_total_priority_fee,
+            )
+            .0
+        };
+
+        // with its signature fee, the small transaction pays more per unit of block space
+        assert!(
+            priority(&small_tx, &small_fee_budget_limits, false)
+                > priority(&large_tx, &large_fee_budget_limits, false)
+        );
+
+        // the large transaction pays the higher total priority fee
+        assert_eq!(priority(&small_tx, &small_fee_budget_limits, true), 10);
+        assert_eq!(priority(&large_tx, &large_fee_budget_limits, true), 2_000);
+    }
 }
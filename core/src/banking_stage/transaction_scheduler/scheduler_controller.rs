@@ -81,6 +81,7 @@ impl<C: LikeClusterInfo, R: ReceiveAndBuffer> SchedulerController<C, R> {
     }
 
     pub fn run(mut self) -> Result<(), SchedulerError> {
+        let mut last_leader_slot = None;
         loop {
             // BufferedPacketsDecision is shared with legacy BankingStage, which will forward
             // packets. Initially, not renaming these decision variants but the actions taken
@@ -104,6 +105,12 @@ impl<C: LikeClusterInfo, R: ReceiveAndBuffer> SchedulerController<C, R> {
                 .maybe_report_and_reset_slot(new_leader_slot);
             self.timing_metrics
                 .maybe_report_and_reset_slot(new_leader_slot);
+            if last_leader_slot != new_leader_slot {
+                if let Some(slot) = last_leader_slot {
+                    self.scheduler.report_metrics(slot);
+                }
+                last_leader_slot = new_leader_slot;
+            }
 
             self.process_transactions(&decision)?;
             self.receive_completed()?;
@@ -163,6 +170,16 @@ impl<C: LikeClusterInfo, R: ReceiveAndBuffer> SchedulerController<C, R> {
                         count_metrics.num_schedule_filtered_out,
                         scheduling_summary.num_filtered_out
                     );
+                    if let Some(max_account_block_count) =
+                        scheduling_summary.blocking_account_counts.values().max()
+                    {
+                        count_metrics.max_account_block_count = count_metrics
+                            .max_account_block_count
+                            .max(*max_account_block_count);
+                    }
+                    count_metrics.max_search_depth = count_metrics
+                        .max_search_depth
+                        .max(scheduling_summary.max_search_depth);
                 });
 
                 self.timing_metrics.update(|timing_metrics| {
@@ -171,6 +188,23 @@ impl<C: LikeClusterInfo, R: ReceiveAndBuffer> SchedulerController<C, R> {
                         scheduling_summary.filter_time_us
                     );
                     saturating_add_assign!(timing_metrics.schedule_time_us, schedule_time_us);
+                    let timings = &scheduling_summary.timings;
+                    saturating_add_assign!(
+                        timing_metrics.schedule_graph_build_time_us,
+                        timings.graph_build_us
+                    );
+                    saturating_add_assign!(
+                        timing_metrics.schedule_lock_check_time_us,
+                        timings.lock_check_us
+                    );
+                    saturating_add_assign!(
+                        timing_metrics.schedule_batching_time_us,
+                        timings.batching_us
+                    );
+                    saturating_add_assign!(timing_metrics.schedule_send_time_us, timings.send_us);
+                    timing_metrics.max_avg_scheduling_latency_us = timing_metrics
+                        .max_avg_scheduling_latency_us
+                        .max(scheduling_summary.avg_scheduling_latency_us);
                 });
             }
             BufferedPacketsDecision::Forward => {
@@ -548,6 +582,7 @@ mod tests {
             packet_deserializer,
             bank_forks.clone(),
             false,
+            false,
         );
 
         let scheduler = PrioGraphScheduler::new(
@@ -77,6 +77,12 @@ pub struct SchedulerCountMetricsInner {
     pub num_dropped_on_age_and_status: usize,
     /// Number of transactions that were dropped due to exceeded capacity.
     pub num_dropped_on_capacity: usize,
+    /// Highest number of times a single account blocked a write during a
+    /// scheduling pass.
+    pub max_account_block_count: usize,
+    /// Highest number of transactions examined to find a schedulable one
+    /// during a scheduling pass.
+    pub max_search_depth: usize,
     /// Min prioritization fees in the transaction container
     pub min_prioritization_fees: u64,
     /// Max prioritization fees in the transaction container
@@ -148,6 +154,8 @@ impl SchedulerCountMetricsInner {
                 i64
             ),
             ("num_dropped_on_capacity", self.num_dropped_on_capacity, i64),
+            ("max_account_block_count", self.max_account_block_count, i64),
+            ("max_search_depth", self.max_search_depth, i64),
             ("min_priority", self.get_min_priority(), i64),
             ("max_priority", self.get_max_priority(), i64)
         );
@@ -191,6 +199,8 @@ impl SchedulerCountMetricsInner {
         self.num_dropped_on_clear = 0;
         self.num_dropped_on_age_and_status = 0;
         self.num_dropped_on_capacity = 0;
+        self.max_account_block_count = 0;
+        self.max_search_depth = 0;
         self.min_prioritization_fees = u64::MAX;
         self.max_prioritization_fees = 0;
     }
@@ -271,6 +281,17 @@ pub struct SchedulerTimingMetricsInner {
     pub schedule_filter_time_us: u64,
     /// Time spent scheduling transactions.
     pub schedule_time_us: u64,
+    /// Time spent building the prio-graph during scheduling.
+    pub schedule_graph_build_time_us: u64,
+    /// Time spent checking account locks during scheduling.
+    pub schedule_lock_check_time_us: u64,
+    /// Time spent batching transactions during scheduling.
+    pub schedule_batching_time_us: u64,
+    /// Time spent sending batches to workers during scheduling.
+    pub schedule_send_time_us: u64,
+    /// Highest per-pass average time transactions waited in the container
+    /// before being scheduled.
+    pub max_avg_scheduling_latency_us: u64,
     /// Time spent clearing transactions from the container.
     pub clear_time_us: u64,
     /// Time spent cleaning expired or processed transactions from the container.
@@ -316,6 +337,27 @@ impl SchedulerTimingMetricsInner {
             ("buffer_time_us", self.buffer_time_us, i64),
             ("schedule_filter_time_us", self.schedule_filter_time_us, i64),
             ("schedule_time_us", self.schedule_time_us, i64),
+            (
+                "schedule_graph_build_time_us",
+                self.schedule_graph_build_time_us,
+                i64
+            ),
+            (
+                "schedule_lock_check_time_us",
+                self.schedule_lock_check_time_us,
+                i64
+            ),
+            (
+                "schedule_batching_time_us",
+                self.schedule_batching_time_us,
+                i64
+            ),
+            ("schedule_send_time_us", self.schedule_send_time_us, i64),
+            (
+                "max_avg_scheduling_latency_us",
+                self.max_avg_scheduling_latency_us,
+                i64
+            ),
             ("clear_time_us", self.clear_time_us, i64),
             ("clean_time_us", self.clean_time_us, i64),
             ("forward_time_us", self.forward_time_us, i64),
@@ -337,6 +379,11 @@ impl SchedulerTimingMetricsInner {
         self.buffer_time_us = 0;
         self.schedule_filter_time_us = 0;
         self.schedule_time_us = 0;
+        self.schedule_graph_build_time_us = 0;
+        self.schedule_lock_check_time_us = 0;
+        self.schedule_batching_time_us = 0;
+        self.schedule_send_time_us = 0;
+        self.max_avg_scheduling_latency_us = 0;
         self.clear_time_us = 0;
         self.clean_time_us = 0;
         self.forward_time_us = 0;
@@ -108,6 +108,11 @@ impl ThreadAwareAccountLocks {
         }
     }
 
+    /// Returns true if any thread holds a lock on the given account.
+    pub(crate) fn is_locked(&self, account: &Pubkey) -> bool {
+        self.locks.contains_key(account)
+    }
+
     /// Returns `ThreadSet` that the given accounts can be scheduled on.
     fn accounts_schedulable_threads<'a>(
         &self,
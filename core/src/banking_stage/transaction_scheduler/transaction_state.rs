@@ -2,7 +2,7 @@ use {
     crate::banking_stage::{
         immutable_deserialized_packet::ImmutableDeserializedPacket, scheduler_messages::MaxAge,
     },
-    std::sync::Arc,
+    std::{sync::Arc, time::Instant},
 };
 
 /// Simple wrapper type to tie a sanitized transaction to max age slot.
@@ -39,6 +39,7 @@ pub(crate) enum TransactionState<Tx> {
         priority: u64,
         cost: u64,
         should_forward: bool,
+        enqueue_time: Instant,
     },
     /// The transaction is currently scheduled or being processed.
     Pending {
@@ -46,6 +47,7 @@ pub(crate) enum TransactionState<Tx> {
         priority: u64,
         cost: u64,
         should_forward: bool,
+        enqueue_time: Instant,
     },
     /// Only used during transition.
     Transitioning,
@@ -67,6 +69,7 @@ impl<Tx> TransactionState<Tx> {
             priority,
             cost,
             should_forward,
+            enqueue_time: Instant::now(),
         }
     }
 
@@ -90,6 +93,15 @@ impl<Tx> TransactionState<Tx> {
         }
     }
 
+    /// Return the time the transaction was first inserted into the container.
+    pub(crate) fn enqueue_time(&self) -> Instant {
+        match self {
+            Self::Unprocessed { enqueue_time, .. } => *enqueue_time,
+            Self::Pending { enqueue_time, .. } => *enqueue_time,
+            Self::Transitioning => unreachable!(),
+        }
+    }
+
     /// Return whether packet should be attempted to be forwarded.
     pub(crate) fn should_forward(&self) -> bool {
         match self {
@@ -139,12 +151,14 @@ impl<Tx> TransactionState<Tx> {
                 priority,
                 cost,
                 should_forward: forwarded,
+                enqueue_time,
             } => {
                 *self = TransactionState::Pending {
                     packet,
                     priority,
                     cost,
                     should_forward: forwarded,
+                    enqueue_time,
                 };
                 transaction_ttl
             }
@@ -172,6 +186,7 @@ impl<Tx> TransactionState<Tx> {
                 priority,
                 cost,
                 should_forward: forwarded,
+                enqueue_time,
             } => {
                 *self = Self::Unprocessed {
                     transaction_ttl,
@@ -179,6 +194,7 @@ impl<Tx> TransactionState<Tx> {
                     priority,
                     cost,
                     should_forward: forwarded,
+                    enqueue_time,
                 }
             }
             Self::Transitioning => unreachable!(),
@@ -61,6 +61,9 @@ pub(crate) trait StateContainer<Tx: TransactionWithMeta> {
     fn get_mut_transaction_state(&mut self, id: TransactionId)
         -> Option<&mut TransactionState<Tx>>;
 
+    /// Get reference to transaction state by id.
+    fn get_transaction_state(&self, id: TransactionId) -> Option<&TransactionState<Tx>>;
+
     /// Get reference to `SanitizedTransactionTTL` by id.
     /// Panics if the transaction does not exist.
     fn get_transaction_ttl(&self, id: TransactionId) -> Option<&SanitizedTransactionTTL<Tx>>;
@@ -126,6 +129,10 @@ impl<Tx: TransactionWithMeta> StateContainer<Tx> for TransactionStateContainer<T
         self.id_to_transaction_state.get_mut(id)
     }
 
+    fn get_transaction_state(&self, id: TransactionId) -> Option<&TransactionState<Tx>> {
+        self.id_to_transaction_state.get(id)
+    }
+
     fn get_transaction_ttl(&self, id: TransactionId) -> Option<&SanitizedTransactionTTL<Tx>> {
         self.id_to_transaction_state
             .get(id)
@@ -190,6 +197,13 @@ impl<Tx: TransactionWithMeta> StateContainer<Tx> for TransactionStateContainer<T
 }
 
 impl<Tx: TransactionWithMeta> TransactionStateContainer<Tx> {
+    /// Returns the ids of all queued transactions, highest priority first.
+    pub(crate) fn queued_ids_by_priority(&self) -> Vec<TransactionPriorityId> {
+        let mut ids: Vec<_> = self.priority_queue.iter().copied().collect();
+        ids.sort_unstable_by(|a, b| b.cmp(a));
+        ids
+    }
+
     fn push_id_into_queue_with_remaining_capacity(
         &mut self,
         priority_id: TransactionPriorityId,
@@ -274,6 +274,9 @@ pub struct ValidatorConfig {
     pub block_verification_method: BlockVerificationMethod,
     pub block_production_method: BlockProductionMethod,
     pub enable_block_production_forwarding: bool,
+    /// Order transactions buffered by the banking stage by their total
+    /// prioritization fee, rather than by reward per unit of cost.
+    pub banking_order_by_total_priority_fee: bool,
     pub generator_config: Option<GeneratorConfig>,
     pub use_snapshot_archives_at_startup: UseSnapshotArchivesAtStartup,
     pub wen_restart_proto_path: Option<PathBuf>,
@@ -346,6 +349,7 @@ impl Default for ValidatorConfig {
             block_verification_method: BlockVerificationMethod::default(),
             block_production_method: BlockProductionMethod::default(),
             enable_block_production_forwarding: false,
+            banking_order_by_total_priority_fee: false,
             generator_config: None,
             use_snapshot_archives_at_startup: UseSnapshotArchivesAtStartup::default(),
             wen_restart_proto_path: None,
@@ -1525,6 +1529,7 @@ impl Validator {
             &prioritization_fee_cache,
             config.block_production_method.clone(),
             config.enable_block_production_forwarding,
+            config.banking_order_by_total_priority_fee,
             config.generator_config.clone(),
         );
 
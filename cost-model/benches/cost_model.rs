@@ -56,3 +56,35 @@ fn bench_cost_model(bencher: &mut Bencher) {
         }
     });
 }
+
+#[bench]
+fn bench_cost_model_block_cost_sequential(bencher: &mut Bencher) {
+    let BenchSetup {
+        transactions,
+        feature_set,
+    } = setup(NUM_TRANSACTIONS_PER_ITER);
+
+    bencher.iter(|| {
+        test::black_box(
+            transactions
+                .iter()
+                .map(|transaction| CostModel::calculate_cost(transaction, &feature_set).sum())
+                .sum::<u64>(),
+        )
+    });
+}
+
+#[bench]
+fn bench_cost_model_block_cost_parallel(bencher: &mut Bencher) {
+    let BenchSetup {
+        transactions,
+        feature_set,
+    } = setup(NUM_TRANSACTIONS_PER_ITER);
+
+    bencher.iter(|| {
+        test::black_box(CostModel::compute_block_cost_parallel(
+            &transactions,
+            &feature_set,
+        ))
+    });
+}
@@ -7,11 +7,13 @@
 
 use {
     crate::{block_cost_limits::*, transaction_cost::*},
+    rayon::prelude::*,
     solana_bincode::limited_deserialize,
     solana_borsh::v1::try_from_slice_unchecked,
     solana_builtins_default_costs::get_builtin_instruction_cost,
     solana_compute_budget::compute_budget_limits::{
-        DEFAULT_HEAP_COST, DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT, MAX_COMPUTE_UNIT_LIMIT,
+        ComputeBudgetLimits, DEFAULT_HEAP_COST, DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT,
+        MAX_COMPUTE_UNIT_LIMIT,
     },
     solana_compute_budget_interface::ComputeBudgetInstruction,
     solana_feature_set::{self as feature_set, FeatureSet},
@@ -39,9 +41,38 @@ enum SystemProgramAccountAllocation {
 }
 
 impl CostModel {
+    /// Returns true if the compute unit limit declared by a transaction alone exceeds
+    /// `block_cu_limit`, such transaction can never fit in a block and may be rejected
+    /// before admission.
+    pub fn exceeds_block_cu_limit(limits: &ComputeBudgetLimits, block_cu_limit: u64) -> bool {
+        u64::from(limits.compute_unit_limit) > block_cu_limit
+    }
+
     pub fn calculate_cost<'a, Tx: TransactionWithMeta>(
         transaction: &'a Tx,
         feature_set: &FeatureSet,
+    ) -> TransactionCost<'a, Tx> {
+        Self::calculate_cost_with_byte_cost(transaction, feature_set, 0)
+    }
+
+    /// Returns the sum of `calculate_cost` over `transactions`, computing each
+    /// transaction's cost in parallel.
+    pub fn compute_block_cost_parallel<Tx: TransactionWithMeta + Sync>(
+        transactions: &[Tx],
+        feature_set: &FeatureSet,
+    ) -> u64 {
+        transactions
+            .par_iter()
+            .map(|transaction| Self::calculate_cost(transaction, feature_set).sum())
+            .reduce(|| 0, u64::saturating_add)
+    }
+
+    /// Same as `calculate_cost`, additionally charging `byte_cost` compute units
+    /// per byte of instruction data on top of the default data bytes cost.
+    pub fn calculate_cost_with_byte_cost<'a, Tx: TransactionWithMeta>(
+        transaction: &'a Tx,
+        feature_set: &FeatureSet,
+        byte_cost: u64,
     ) -> TransactionCost<'a, Tx> {
         if transaction.is_simple_vote_transaction() {
             TransactionCost::SimpleVote { transaction }
@@ -52,6 +83,10 @@ impl CostModel {
                     transaction.program_instructions_iter(),
                     feature_set,
                 );
+            let data_bytes_cost = data_bytes_cost.saturating_add(
+                Self::get_instructions_data_len(transaction.program_instructions_iter())
+                    .saturating_mul(byte_cost),
+            );
             Self::calculate_non_vote_transaction_cost(
                 transaction,
                 transaction.program_instructions_iter(),
@@ -313,11 +348,16 @@ impl CostModel {
     fn get_instructions_data_cost<'a>(
         instructions: impl Iterator<Item = (&'a Pubkey, SVMInstruction<'a>)>,
     ) -> u64 {
-        let ix_data_bytes_len_total: u64 = instructions
-            .map(|(_, instruction)| instruction.data.len() as u64)
-            .sum();
+        Self::get_instructions_data_len(instructions) / INSTRUCTION_DATA_BYTES_COST
+    }
 
-        ix_data_bytes_len_total / INSTRUCTION_DATA_BYTES_COST
+    /// Return the total length of instruction data, in bytes.
+    fn get_instructions_data_len<'a>(
+        instructions: impl Iterator<Item = (&'a Pubkey, SVMInstruction<'a>)>,
+    ) -> u64 {
+        instructions
+            .map(|(_, instruction)| instruction.data.len() as u64)
+            .sum()
     }
 
     pub fn calculate_loaded_accounts_data_size_cost(
@@ -402,6 +442,7 @@ impl CostModel {
 mod tests {
     use {
         super::*,
+        crate::cost_tracker::CostTracker,
         itertools::Itertools,
         solana_compute_budget::{
             self,
@@ -421,7 +462,7 @@ mod tests {
         solana_svm_transaction::svm_message::SVMMessage,
         solana_system_interface::instruction::{self as system_instruction},
         solana_system_transaction as system_transaction,
-        solana_transaction::Transaction,
+        solana_transaction::{sanitized::SanitizedTransaction, Transaction},
     };
 
     fn test_setup() -> (Keypair, Hash) {
@@ -610,6 +651,139 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_cost_model_byte_cost() {
+        let (mint_keypair, start_hash) = test_setup();
+        let program_id = Pubkey::new_unique();
+        let [small_tx, large_tx] = [10, 1_000].map(|data_len| {
+            RuntimeTransaction::from_transaction_for_tests(Transaction::new_signed_with_payer(
+                &[Instruction::new_with_bytes(
+                    program_id,
+                    &vec![0; data_len],
+                    vec![],
+                )],
+                Some(&mint_keypair.pubkey()),
+                &[&mint_keypair],
+                start_hash,
+            ))
+        });
+
+        for feature_set in [FeatureSet::default(), FeatureSet::all_enabled()] {
+            // default byte cost does not change the cost
+            assert_eq!(
+                CostModel::calculate_cost(&large_tx, &feature_set).sum(),
+                CostModel::calculate_cost_with_byte_cost(&large_tx, &feature_set, 0).sum()
+            );
+
+            let byte_cost = 3;
+            let cost_diff = |byte_cost| {
+                CostModel::calculate_cost_with_byte_cost(&large_tx, &feature_set, byte_cost).sum()
+                    - CostModel::calculate_cost_with_byte_cost(&small_tx, &feature_set, byte_cost)
+                        .sum()
+            };
+            assert_eq!(cost_diff(byte_cost), cost_diff(0) + 990 * byte_cost);
+        }
+    }
+
+    #[test]
+    fn test_cost_model_exceeds_block_cu_limit() {
+        let (mint_keypair, start_hash) = test_setup();
+        let feature_set = FeatureSet::all_enabled();
+        let tx =
+            RuntimeTransaction::from_transaction_for_tests(Transaction::new_signed_with_payer(
+                &[
+                    ComputeBudgetInstruction::set_compute_unit_limit(MAX_COMPUTE_UNIT_LIMIT),
+                    system_instruction::transfer(&mint_keypair.pubkey(), &Pubkey::new_unique(), 1),
+                ],
+                Some(&mint_keypair.pubkey()),
+                &[&mint_keypair],
+                start_hash,
+            ));
+        let limits = tx
+            .compute_budget_instruction_details()
+            .sanitize_and_convert_to_compute_budget_limits(&feature_set)
+            .unwrap();
+
+        // declares more compute units than an entire block allows
+        assert!(CostModel::exceeds_block_cu_limit(
+            &limits,
+            u64::from(MAX_COMPUTE_UNIT_LIMIT) - 1
+        ));
+        assert!(!CostModel::exceeds_block_cu_limit(
+            &limits,
+            u64::from(MAX_COMPUTE_UNIT_LIMIT)
+        ));
+        assert!(!CostModel::exceeds_block_cu_limit(&limits, MAX_BLOCK_UNITS));
+    }
+
+    #[test]
+    fn test_cost_model_compute_block_cost_parallel() {
+        let (mint_keypair, start_hash) = test_setup();
+        let transactions: Vec<_> = (0..64)
+            .map(|i| {
+                let to_lamports: Vec<_> = (0..i % 8 + 1)
+                    .map(|_| (solana_pubkey::new_rand(), 1))
+                    .collect();
+                let ixs = system_instruction::transfer_many(&mint_keypair.pubkey(), &to_lamports);
+                let message = Message::new(&ixs, Some(&mint_keypair.pubkey()));
+                RuntimeTransaction::from_transaction_for_tests(Transaction::new(
+                    &[&mint_keypair],
+                    message,
+                    start_hash,
+                ))
+            })
+            .collect();
+
+        for feature_set in [FeatureSet::default(), FeatureSet::all_enabled()] {
+            let sequential_cost = transactions
+                .iter()
+                .map(|transaction| CostModel::calculate_cost(transaction, &feature_set).sum())
+                .sum::<u64>();
+            assert!(sequential_cost > 0);
+            assert_eq!(
+                sequential_cost,
+                CostModel::compute_block_cost_parallel(&transactions, &feature_set)
+            );
+        }
+        assert_eq!(
+            0,
+            CostModel::compute_block_cost_parallel::<RuntimeTransaction<SanitizedTransaction>>(
+                &[],
+                &FeatureSet::default()
+            )
+        );
+    }
+
+    #[test]
+    fn test_cost_model_transaction_without_instructions() {
+        let (mint_keypair, start_hash) = test_setup();
+        let tx = RuntimeTransaction::from_transaction_for_tests(Transaction::new(
+            &[&mint_keypair],
+            Message::new(&[], Some(&mint_keypair.pubkey())),
+            start_hash,
+        ));
+        assert_eq!(0, tx.program_instructions_iter().count());
+
+        // An instruction-less transaction has no execution cost, but is still
+        // charged for its signature and fee-payer write lock, so it never
+        // fits into a block for free.
+        for feature_set in [FeatureSet::default(), FeatureSet::all_enabled()] {
+            let tx_cost = CostModel::calculate_cost(&tx, &feature_set);
+            assert_eq!(0, tx_cost.programs_execution_cost());
+            assert_eq!(SIGNATURE_COST, tx_cost.signature_cost());
+            assert_eq!(WRITE_LOCK_UNITS, tx_cost.write_lock_cost());
+            assert_eq!(
+                SIGNATURE_COST + WRITE_LOCK_UNITS + tx_cost.loaded_accounts_data_size_cost(),
+                tx_cost.sum()
+            );
+
+            let mut cost_tracker = CostTracker::default();
+            assert!(cost_tracker.try_add(&tx_cost).is_ok());
+            assert_eq!(tx_cost.sum(), cost_tracker.block_cost());
+            assert_eq!(1, cost_tracker.transaction_count());
+        }
+    }
+
     #[test]
     fn test_cost_model_simple_transaction() {
         let (mint_keypair, start_hash) = test_setup();
@@ -640,6 +814,81 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_cost_model_signature_cost() {
+        let (mint_keypair, start_hash) = test_setup();
+        let to_pubkey = Pubkey::new_unique();
+
+        let single_signer_transaction = RuntimeTransaction::from_transaction_for_tests(
+            system_transaction::transfer(&mint_keypair, &to_pubkey, 2, start_hash),
+        );
+
+        let other_keypair = Keypair::new();
+        let multi_signer_transaction =
+            RuntimeTransaction::from_transaction_for_tests(Transaction::new_signed_with_payer(
+                &[
+                    system_instruction::transfer(&mint_keypair.pubkey(), &to_pubkey, 2),
+                    system_instruction::transfer(&other_keypair.pubkey(), &to_pubkey, 2),
+                ],
+                Some(&mint_keypair.pubkey()),
+                &[&mint_keypair, &other_keypair],
+                start_hash,
+            ));
+
+        for feature_set in [FeatureSet::default(), FeatureSet::all_enabled()] {
+            let single_signer_cost =
+                CostModel::calculate_cost(&single_signer_transaction, &feature_set);
+            let multi_signer_cost =
+                CostModel::calculate_cost(&multi_signer_transaction, &feature_set);
+
+            assert_eq!(SIGNATURE_COST, single_signer_cost.signature_cost());
+            assert_eq!(2 * SIGNATURE_COST, multi_signer_cost.signature_cost());
+            assert_eq!(
+                SIGNATURE_COST,
+                multi_signer_cost.signature_cost() - single_signer_cost.signature_cost()
+            );
+        }
+    }
+
+    #[test]
+    fn test_cost_model_account_referenced_twice() {
+        let (mint_keypair, start_hash) = test_setup();
+        let to_pubkey = Pubkey::new_unique();
+
+        // recipient is referenced by both instructions
+        let transaction =
+            RuntimeTransaction::from_transaction_for_tests(Transaction::new_signed_with_payer(
+                &[
+                    system_instruction::transfer(&mint_keypair.pubkey(), &to_pubkey, 2),
+                    system_instruction::transfer(&mint_keypair.pubkey(), &to_pubkey, 3),
+                ],
+                Some(&mint_keypair.pubkey()),
+                &[&mint_keypair],
+                start_hash,
+            ));
+
+        for feature_set in [FeatureSet::default(), FeatureSet::all_enabled()] {
+            let tx_cost = CostModel::calculate_cost(&transaction, &feature_set);
+            assert_eq!(2 * WRITE_LOCK_UNITS, tx_cost.write_lock_cost());
+            assert_eq!(
+                vec![&mint_keypair.pubkey(), &to_pubkey],
+                tx_cost.writable_accounts().collect::<Vec<_>>()
+            );
+
+            // recipient's chained cost is charged once
+            let mut cost_tracker = CostTracker::default();
+            assert!(cost_tracker.try_add(&tx_cost).is_ok());
+            assert_eq!(
+                vec![(to_pubkey, tx_cost.sum())],
+                cost_tracker
+                    .top_accounts(2)
+                    .into_iter()
+                    .filter(|(account, _cost)| *account == to_pubkey)
+                    .collect::<Vec<_>>()
+            );
+        }
+    }
+
     #[test]
     fn test_cost_model_token_transaction() {
         let (mint_keypair, start_hash) = test_setup();
@@ -848,6 +1097,39 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_cost_model_many_user_space_instructions_clamped_to_max_limit() {
+        let (mint_keypair, start_hash) = test_setup();
+
+        // enough default-cost instructions that their summed cost exceeds the
+        // max compute unit limit; the cost is clamped to the limit.
+        let key = solana_pubkey::new_rand();
+        let prog = solana_pubkey::new_rand();
+        let num_instructions = 250;
+        let instructions = (0..num_instructions)
+            .map(|_| CompiledInstruction::new(2, &(), vec![0, 1]))
+            .collect();
+        let tx = RuntimeTransaction::from_transaction_for_tests(
+            Transaction::new_with_compiled_instructions(
+                &[&mint_keypair],
+                &[key],
+                start_hash,
+                vec![prog],
+                instructions,
+            ),
+        );
+        assert!(
+            num_instructions * u64::from(DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT)
+                > u64::from(MAX_COMPUTE_UNIT_LIMIT)
+        );
+
+        for feature_set in [FeatureSet::default(), FeatureSet::all_enabled()] {
+            let (program_execution_cost, _loaded_accounts_data_size_cost, _data_bytes_cost) =
+                CostModel::get_transaction_cost(&tx, tx.program_instructions_iter(), &feature_set);
+            assert_eq!(u64::from(MAX_COMPUTE_UNIT_LIMIT), program_execution_cost);
+        }
+    }
+
     #[test]
     fn test_cost_model_sort_message_accounts_by_type() {
         // construct a transaction with two random instructions with same signer
@@ -13,6 +13,11 @@ use {
 };
 
 const WRITABLE_ACCOUNTS_PER_BLOCK: usize = 4096;
+/// Default cap on the number of writable accounts a single transaction may
+/// charge. Matches `MAX_TX_ACCOUNT_LOCKS`; a bank sets its tracker's cap from
+/// its own transaction account lock limit, which may be lower before the
+/// limit increase feature activates, or higher when overridden by the node.
+pub const DEFAULT_MAX_WRITABLE_ACCOUNTS_PER_TRANSACTION: usize = 128;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CostTrackerError {
@@ -30,6 +35,9 @@ pub enum CostTrackerError {
 
     /// would exceed account data total limit
     WouldExceedAccountDataTotalLimit,
+
+    /// would exceed max writable accounts per transaction
+    WouldExceedWritableAccountsLimit,
 }
 
 impl From<CostTrackerError> for TransactionError {
@@ -44,10 +52,20 @@ impl From<CostTrackerError> for TransactionError {
             CostTrackerError::WouldExceedAccountDataTotalLimit => {
                 Self::WouldExceedAccountDataTotalLimit
             }
+            CostTrackerError::WouldExceedWritableAccountsLimit => Self::TooManyAccountLocks,
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CostLimitsError {
+    /// account cost limit is greater than block cost limit
+    AccountLimitExceedsBlockLimit,
+
+    /// vote cost limit is greater than block cost limit
+    VoteLimitExceedsBlockLimit,
+}
+
 /// Relevant block costs that were updated after successful `try_add()`
 #[derive(Debug, Default)]
 pub struct UpdatedCosts {
@@ -57,14 +75,60 @@ pub struct UpdatedCosts {
     pub updated_costliest_account_cost: u64,
 }
 
+/// Costs of batch transactions that have been checked by `try_add_batch()`
+/// but not yet added to the tracker.
+#[derive(Debug, Default)]
+struct PendingCosts {
+    block_cost: u64,
+    vote_cost: u64,
+    allocated_accounts_data_size: u64,
+    cost_by_writable_accounts: HashMap<Pubkey, u64>,
+}
+
+impl PendingCosts {
+    fn add(&mut self, tx_cost: &TransactionCost<impl TransactionWithMeta>) {
+        let cost = tx_cost.sum();
+        self.block_cost = self.block_cost.saturating_add(cost);
+        if tx_cost.is_simple_vote() {
+            self.vote_cost = self.vote_cost.saturating_add(cost);
+        }
+        self.allocated_accounts_data_size = self
+            .allocated_accounts_data_size
+            .saturating_add(tx_cost.allocated_accounts_data_size());
+        for account_key in tx_cost.writable_accounts() {
+            let account_cost = self
+                .cost_by_writable_accounts
+                .entry(*account_key)
+                .or_insert(0);
+            *account_cost = account_cost.saturating_add(cost);
+        }
+    }
+}
+
+/// Summary of the costs accumulated by a `CostTracker`, as returned by
+/// `reset()` for the block being closed.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CostTrackerStats {
+    pub block_cost: u64,
+    pub vote_cost: u64,
+    pub transaction_count: u64,
+    pub number_of_accounts: usize,
+    pub costliest_account: Pubkey,
+    pub costliest_account_cost: u64,
+}
+
 #[cfg_attr(feature = "frozen-abi", derive(AbiExample))]
 #[derive(Debug)]
 pub struct CostTracker {
     account_cost_limit: u64,
     block_cost_limit: u64,
     vote_cost_limit: u64,
+    max_writable_accounts_per_transaction: usize,
     cost_by_writable_accounts: HashMap<Pubkey, u64, ahash::RandomState>,
     block_cost: u64,
+    /// Portion of `block_cost` charged by transactions without writable
+    /// accounts, which is not reflected in `cost_by_writable_accounts`.
+    cost_without_writable_accounts: u64,
     vote_cost: u64,
     transaction_count: Saturating<u64>,
     allocated_accounts_data_size: Saturating<u64>,
@@ -90,11 +154,13 @@ impl Default for CostTracker {
             account_cost_limit: MAX_WRITABLE_ACCOUNT_UNITS,
             block_cost_limit: MAX_BLOCK_UNITS,
             vote_cost_limit: MAX_VOTE_UNITS,
+            max_writable_accounts_per_transaction: DEFAULT_MAX_WRITABLE_ACCOUNTS_PER_TRANSACTION,
             cost_by_writable_accounts: HashMap::with_capacity_and_hasher(
                 WRITABLE_ACCOUNTS_PER_BLOCK,
                 ahash::RandomState::new(),
             ),
             block_cost: 0,
+            cost_without_writable_accounts: 0,
             vote_cost: 0,
             transaction_count: Saturating(0),
             allocated_accounts_data_size: Saturating(0),
@@ -108,6 +174,32 @@ impl Default for CostTracker {
 }
 
 impl CostTracker {
+    /// Create a tracker with given limits, panics if account or vote cost limit is greater than
+    /// block cost limit.
+    pub fn new(account_cost_limit: u64, block_cost_limit: u64, vote_cost_limit: u64) -> Self {
+        Self::try_new(account_cost_limit, block_cost_limit, vote_cost_limit)
+            .expect("account and vote cost limits must not exceed block cost limit")
+    }
+
+    /// Create a tracker with given limits, returns error if account or vote cost limit is
+    /// greater than block cost limit.
+    pub fn try_new(
+        account_cost_limit: u64,
+        block_cost_limit: u64,
+        vote_cost_limit: u64,
+    ) -> Result<Self, CostLimitsError> {
+        if account_cost_limit > block_cost_limit {
+            return Err(CostLimitsError::AccountLimitExceedsBlockLimit);
+        }
+        if vote_cost_limit > block_cost_limit {
+            return Err(CostLimitsError::VoteLimitExceedsBlockLimit);
+        }
+
+        let mut new = Self::default();
+        new.set_limits(account_cost_limit, block_cost_limit, vote_cost_limit);
+        Ok(new)
+    }
+
     pub fn new_from_parent_limits(&self) -> Self {
         let mut new = Self::default();
         new.set_limits(
@@ -115,12 +207,26 @@ impl CostTracker {
             self.block_cost_limit,
             self.vote_cost_limit,
         );
+        new.max_writable_accounts_per_transaction = self.max_writable_accounts_per_transaction;
         new
     }
 
-    pub fn reset(&mut self) {
+    /// Clears all accumulated costs, keeping the configured limits. Returns
+    /// the stats accumulated before the reset.
+    pub fn reset(&mut self) -> CostTrackerStats {
+        let (costliest_account, costliest_account_cost) = self.find_costliest_account();
+        let stats = CostTrackerStats {
+            block_cost: self.block_cost,
+            vote_cost: self.vote_cost,
+            transaction_count: self.transaction_count.0,
+            number_of_accounts: self.number_of_accounts(),
+            costliest_account,
+            costliest_account_cost,
+        };
+
         self.cost_by_writable_accounts.clear();
         self.block_cost = 0;
+        self.cost_without_writable_accounts = 0;
         self.vote_cost = 0;
         self.transaction_count = Saturating(0);
         self.allocated_accounts_data_size = Saturating(0);
@@ -128,6 +234,7 @@ impl CostTracker {
         self.secp256k1_instruction_signature_count = Saturating(0);
         self.ed25519_instruction_signature_count = Saturating(0);
         self.in_flight_transaction_count = Saturating(0);
+        stats
     }
 
     /// Get the overall block limit.
@@ -147,6 +254,17 @@ impl CostTracker {
         self.vote_cost_limit = vote_cost_limit;
     }
 
+    /// Get the maximum number of writable accounts a single transaction may charge.
+    pub fn get_max_writable_accounts_per_transaction(&self) -> usize {
+        self.max_writable_accounts_per_transaction
+    }
+
+    /// Sets the maximum number of writable accounts a single transaction may
+    /// charge; transactions writing to more accounts are rejected.
+    pub fn set_max_writable_accounts_per_transaction(&mut self, max_writable_accounts: usize) {
+        self.max_writable_accounts_per_transaction = max_writable_accounts;
+    }
+
     pub fn in_flight_transaction_count(&self) -> usize {
         self.in_flight_transaction_count.0
     }
@@ -171,6 +289,27 @@ impl CostTracker {
         })
     }
 
+    /// Adds a batch of transactions all-or-nothing: either every transaction in the batch fits
+    /// and is added, or none is. On failure returns the index of the first transaction that
+    /// would not fit, accounting for costs of preceding transactions in the same batch.
+    pub fn try_add_batch<Tx: TransactionWithMeta>(
+        &mut self,
+        batch: &[TransactionCost<Tx>],
+    ) -> Result<(), usize> {
+        let mut pending = PendingCosts::default();
+        for (index, tx_cost) in batch.iter().enumerate() {
+            if self.would_fit_with_pending(tx_cost, &pending).is_err() {
+                return Err(index);
+            }
+            pending.add(tx_cost);
+        }
+
+        for tx_cost in batch {
+            self.add_transaction_cost(tx_cost);
+        }
+        Ok(())
+    }
+
     pub fn update_execution_cost(
         &mut self,
         estimated_tx_cost: &TransactionCost<impl TransactionWithMeta>,
@@ -203,6 +342,28 @@ impl CostTracker {
         self.remove_transaction_cost(tx_cost);
     }
 
+    /// Sanity checks block cost against accumulated writable account costs, returns false if
+    /// costs have drifted, e.g. a transaction was removed without having been added.
+    ///
+    /// Every transaction charges its cost to the block and to each of its writable accounts, so
+    /// no account cost can exceed block cost, and block cost can't exceed the sum of all account
+    /// costs plus the cost of transactions without writable accounts. If every transaction writes
+    /// at most one account, block cost equals that sum exactly. Transactions writing multiple
+    /// accounts raise the sum above block cost, so drift within the gap is not detected.
+    ///
+    /// Debug builds assert this after every transaction is added or removed.
+    pub fn verify_consistency(&self) -> bool {
+        let (max_account_cost, sum_account_costs) = self
+            .cost_by_writable_accounts
+            .values()
+            .fold((0u64, 0u128), |(max, sum), cost| {
+                (max.max(*cost), sum.saturating_add(u128::from(*cost)))
+            });
+        max_account_cost <= self.block_cost
+            && u128::from(self.block_cost)
+                <= sum_account_costs.saturating_add(u128::from(self.cost_without_writable_accounts))
+    }
+
     pub fn block_cost(&self) -> u64 {
         self.block_cost
     }
@@ -215,6 +376,52 @@ impl CostTracker {
         self.transaction_count.0
     }
 
+    /// Returns the cost that can still be added before reaching the block cost limit.
+    pub fn remaining_block_cost(&self) -> u64 {
+        self.block_cost_limit.saturating_sub(self.block_cost)
+    }
+
+    /// Returns true if block cost has reached `threshold_ratio` of the block cost limit, a
+    /// leader may stop packing the block once it is nearly full.
+    pub fn is_block_nearly_full(&self, threshold_ratio: f64) -> bool {
+        let block_fill_ratio = if self.block_cost_limit == 0 {
+            1.0
+        } else {
+            self.block_cost as f64 / self.block_cost_limit as f64
+        };
+        block_fill_ratio >= threshold_ratio
+    }
+
+    /// Returns up to `n` writable accounts with the highest accumulated cost, sorted by cost in
+    /// descending order.
+    pub fn top_accounts(&self, n: usize) -> Vec<(Pubkey, u64)> {
+        let mut accounts: Vec<_> = self
+            .cost_by_writable_accounts
+            .iter()
+            .filter(|(_, &cost)| cost > 0)
+            .map(|(&pubkey, &cost)| (pubkey, cost))
+            .collect();
+        accounts.sort_unstable_by(|(pubkey_a, cost_a), (pubkey_b, cost_b)| {
+            cost_b.cmp(cost_a).then_with(|| pubkey_a.cmp(pubkey_b))
+        });
+        accounts.truncate(n);
+        accounts
+    }
+
+    /// Returns those of `keys` that already have nonzero accumulated cost in this block, in the
+    /// order given. Transactions writing to any of them are serialized behind the existing
+    /// cost chain.
+    pub fn accounts_with_existing_cost(&self, keys: &[Pubkey]) -> Vec<Pubkey> {
+        keys.iter()
+            .filter(|key| {
+                self.cost_by_writable_accounts
+                    .get(*key)
+                    .is_some_and(|cost| *cost > 0)
+            })
+            .copied()
+            .collect()
+    }
+
     pub fn report_stats(&self, bank_slot: solana_clock::Slot) {
         // skip reporting if block is empty
         if self.transaction_count.0 == 0 {
@@ -276,17 +483,36 @@ impl CostTracker {
     fn would_fit(
         &self,
         tx_cost: &TransactionCost<impl TransactionWithMeta>,
+    ) -> Result<(), CostTrackerError> {
+        self.would_fit_with_pending(tx_cost, &PendingCosts::default())
+    }
+
+    /// Same as `would_fit()`, with `pending` costs counted as if they were already added.
+    fn would_fit_with_pending(
+        &self,
+        tx_cost: &TransactionCost<impl TransactionWithMeta>,
+        pending: &PendingCosts,
     ) -> Result<(), CostTrackerError> {
         let cost: u64 = tx_cost.sum();
 
         if tx_cost.is_simple_vote() {
             // if vote transaction, check if it exceeds vote_transaction_limit
-            if self.vote_cost.saturating_add(cost) > self.vote_cost_limit {
+            if self
+                .vote_cost
+                .saturating_add(pending.vote_cost)
+                .saturating_add(cost)
+                > self.vote_cost_limit
+            {
                 return Err(CostTrackerError::WouldExceedVoteMaxLimit);
             }
         }
 
-        if self.block_cost.saturating_add(cost) > self.block_cost_limit {
+        if self
+            .block_cost
+            .saturating_add(pending.block_cost)
+            .saturating_add(cost)
+            > self.block_cost_limit
+        {
             // check against the total package cost
             return Err(CostTrackerError::WouldExceedBlockMaxLimit);
         }
@@ -296,24 +522,34 @@ impl CostTracker {
             return Err(CostTrackerError::WouldExceedAccountMaxLimit);
         }
 
-        let allocated_accounts_data_size =
-            self.allocated_accounts_data_size + Saturating(tx_cost.allocated_accounts_data_size());
+        let allocated_accounts_data_size = self.allocated_accounts_data_size
+            + Saturating(pending.allocated_accounts_data_size)
+            + Saturating(tx_cost.allocated_accounts_data_size());
 
         if allocated_accounts_data_size.0 > MAX_BLOCK_ACCOUNTS_DATA_SIZE_DELTA {
             return Err(CostTrackerError::WouldExceedAccountDataBlockLimit);
         }
 
-        // check each account against account_cost_limit,
-        for account_key in tx_cost.writable_accounts() {
-            match self.cost_by_writable_accounts.get(account_key) {
-                Some(chained_cost) => {
-                    if chained_cost.saturating_add(cost) > self.account_cost_limit {
-                        return Err(CostTrackerError::WouldExceedAccountMaxLimit);
-                    } else {
-                        continue;
-                    }
-                }
-                None => continue,
+        // check each account against account_cost_limit, and that the transaction does not
+        // charge too many writable accounts
+        for (index, account_key) in tx_cost.writable_accounts().enumerate() {
+            if index >= self.max_writable_accounts_per_transaction {
+                return Err(CostTrackerError::WouldExceedWritableAccountsLimit);
+            }
+            let chained_cost = self
+                .cost_by_writable_accounts
+                .get(account_key)
+                .copied()
+                .unwrap_or_default()
+                .saturating_add(
+                    pending
+                        .cost_by_writable_accounts
+                        .get(account_key)
+                        .copied()
+                        .unwrap_or_default(),
+                );
+            if chained_cost.saturating_add(cost) > self.account_cost_limit {
+                return Err(CostTrackerError::WouldExceedAccountMaxLimit);
             }
         }
 
@@ -330,7 +566,9 @@ impl CostTracker {
         self.ed25519_instruction_signature_count += tx_cost.num_ed25519_instruction_signatures();
         self.secp256r1_instruction_signature_count +=
             tx_cost.num_secp256r1_instruction_signatures();
-        self.add_transaction_execution_cost(tx_cost, tx_cost.sum())
+        let costliest_account_cost = self.add_transaction_execution_cost(tx_cost, tx_cost.sum());
+        debug_assert!(self.verify_consistency());
+        costliest_account_cost
     }
 
     fn remove_transaction_cost(&mut self, tx_cost: &TransactionCost<impl TransactionWithMeta>) {
@@ -344,6 +582,7 @@ impl CostTracker {
         self.ed25519_instruction_signature_count -= tx_cost.num_ed25519_instruction_signatures();
         self.secp256r1_instruction_signature_count -=
             tx_cost.num_secp256r1_instruction_signatures();
+        debug_assert!(self.verify_consistency());
     }
 
     /// Apply additional actual execution units to cost_tracker
@@ -354,6 +593,7 @@ impl CostTracker {
         adjustment: u64,
     ) -> u64 {
         let mut costliest_account_cost = 0;
+        let mut has_writable_accounts = false;
         for account_key in tx_cost.writable_accounts() {
             let account_cost = self
                 .cost_by_writable_accounts
@@ -361,6 +601,12 @@ impl CostTracker {
                 .or_insert(0);
             *account_cost = account_cost.saturating_add(adjustment);
             costliest_account_cost = costliest_account_cost.max(*account_cost);
+            has_writable_accounts = true;
+        }
+        if !has_writable_accounts {
+            self.cost_without_writable_accounts = self
+                .cost_without_writable_accounts
+                .saturating_add(adjustment);
         }
         self.block_cost = self.block_cost.saturating_add(adjustment);
         if tx_cost.is_simple_vote() {
@@ -376,12 +622,19 @@ impl CostTracker {
         tx_cost: &TransactionCost<impl TransactionWithMeta>,
         adjustment: u64,
     ) {
+        let mut has_writable_accounts = false;
         for account_key in tx_cost.writable_accounts() {
             let account_cost = self
                 .cost_by_writable_accounts
                 .entry(*account_key)
                 .or_insert(0);
             *account_cost = account_cost.saturating_sub(adjustment);
+            has_writable_accounts = true;
+        }
+        if !has_writable_accounts {
+            self.cost_without_writable_accounts = self
+                .cost_without_writable_accounts
+                .saturating_sub(adjustment);
         }
         self.block_cost = self.block_cost.saturating_sub(adjustment);
         if tx_cost.is_simple_vote() {
@@ -408,19 +661,6 @@ mod tests {
         std::cmp,
     };
 
-    impl CostTracker {
-        fn new(account_cost_limit: u64, block_cost_limit: u64, vote_cost_limit: u64) -> Self {
-            assert!(account_cost_limit <= block_cost_limit);
-            assert!(vote_cost_limit <= block_cost_limit);
-            Self {
-                account_cost_limit,
-                block_cost_limit,
-                vote_cost_limit,
-                ..Self::default()
-            }
-        }
-    }
-
     fn test_setup() -> Keypair {
         solana_logger::setup();
         Keypair::new()
@@ -471,6 +711,26 @@ mod tests {
         assert_eq!(0, testee.block_cost);
     }
 
+    #[test]
+    fn test_cost_tracker_try_new() {
+        assert!(CostTracker::try_new(10, 11, 8).is_ok());
+        assert!(CostTracker::try_new(11, 11, 11).is_ok());
+        assert_eq!(
+            CostTracker::try_new(12, 11, 8).unwrap_err(),
+            CostLimitsError::AccountLimitExceedsBlockLimit
+        );
+        assert_eq!(
+            CostTracker::try_new(10, 11, 12).unwrap_err(),
+            CostLimitsError::VoteLimitExceedsBlockLimit
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "account and vote cost limits must not exceed block cost limit")]
+    fn test_cost_tracker_new_invalid_limits() {
+        CostTracker::new(12, 11, 8);
+    }
+
     #[test]
     fn test_cost_tracker_ok_add_one() {
         let mint_keypair = test_setup();
@@ -581,6 +841,100 @@ mod tests {
         assert_eq!(std::cmp::max(cost1, cost2), costliest_account_cost);
     }
 
+    #[test]
+    fn test_cost_tracker_is_block_nearly_full() {
+        let threshold_ratio = 0.9;
+        let mut testee = CostTracker::new(100, 100, 100);
+        assert!(!testee.is_block_nearly_full(threshold_ratio));
+
+        // just below threshold
+        let tx1 = WritableKeysTransaction(vec![Pubkey::new_unique()]);
+        testee.add_transaction_cost(&simple_transaction_cost(&tx1, 89));
+        assert!(!testee.is_block_nearly_full(threshold_ratio));
+
+        // just above threshold
+        let tx2 = WritableKeysTransaction(vec![Pubkey::new_unique()]);
+        testee.add_transaction_cost(&simple_transaction_cost(&tx2, 2));
+        assert!(testee.is_block_nearly_full(threshold_ratio));
+    }
+
+    #[test]
+    fn test_cost_tracker_remaining_block_cost() {
+        let mut testee = CostTracker::new(100, 100, 100);
+        assert_eq!(100, testee.remaining_block_cost());
+
+        let tx1 = WritableKeysTransaction(vec![Pubkey::new_unique()]);
+        testee.add_transaction_cost(&simple_transaction_cost(&tx1, 30));
+        assert_eq!(70, testee.remaining_block_cost());
+
+        let tx2 = WritableKeysTransaction(vec![Pubkey::new_unique()]);
+        testee.add_transaction_cost(&simple_transaction_cost(&tx2, 70));
+        assert_eq!(0, testee.remaining_block_cost());
+
+        // lowering the limit below current block cost does not underflow
+        testee.set_limits(50, 50, 50);
+        assert_eq!(0, testee.remaining_block_cost());
+    }
+
+    #[test]
+    fn test_cost_tracker_top_accounts() {
+        let mut testee = CostTracker::default();
+        assert!(testee.top_accounts(3).is_empty());
+
+        let account_costs = [
+            (Pubkey::new_unique(), 20),
+            (Pubkey::new_unique(), 50),
+            (Pubkey::new_unique(), 10),
+            (Pubkey::new_unique(), 40),
+        ];
+        for (account, cost) in account_costs {
+            let tx = WritableKeysTransaction(vec![account]);
+            testee.add_transaction_cost(&simple_transaction_cost(&tx, cost));
+        }
+
+        // truncated to the top n, sorted by cost descending
+        assert_eq!(
+            vec![(account_costs[1].0, 50), (account_costs[3].0, 40)],
+            testee.top_accounts(2)
+        );
+        assert!(testee.top_accounts(0).is_empty());
+
+        // n larger than number of accounts returns all of them
+        assert_eq!(
+            vec![
+                (account_costs[1].0, 50),
+                (account_costs[3].0, 40),
+                (account_costs[0].0, 20),
+                (account_costs[2].0, 10),
+            ],
+            testee.top_accounts(10)
+        );
+    }
+
+    #[test]
+    fn test_cost_tracker_accounts_with_existing_cost() {
+        let mut testee = CostTracker::default();
+        let (acct1, acct2, acct3) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        assert!(testee.accounts_with_existing_cost(&[acct1]).is_empty());
+
+        let tx = WritableKeysTransaction(vec![acct1, acct2]);
+        testee.add_transaction_cost(&simple_transaction_cost(&tx, 5));
+
+        assert_eq!(
+            vec![acct2],
+            testee.accounts_with_existing_cost(&[acct3, acct2])
+        );
+        assert_eq!(
+            vec![acct1, acct2],
+            testee.accounts_with_existing_cost(&[acct1, acct2, acct3])
+        );
+        assert!(testee.accounts_with_existing_cost(&[]).is_empty());
+    }
+
     #[test]
     fn test_cost_tracker_chain_reach_limit() {
         let mint_keypair = test_setup();
@@ -695,6 +1049,72 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_cost_tracker_reach_writable_accounts_limit() {
+        let mut testee = CostTracker::default();
+        testee.set_max_writable_accounts_per_transaction(2);
+
+        let transaction = WritableKeysTransaction(vec![Pubkey::new_unique(), Pubkey::new_unique()]);
+        let tx_cost = simple_transaction_cost(&transaction, 5);
+        assert!(testee.try_add(&tx_cost).is_ok());
+
+        let transaction = WritableKeysTransaction(vec![
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        ]);
+        let tx_cost = simple_transaction_cost(&transaction, 5);
+        assert_eq!(
+            testee.try_add(&tx_cost).unwrap_err(),
+            CostTrackerError::WouldExceedWritableAccountsLimit
+        );
+        assert_eq!(5, testee.block_cost);
+        assert_eq!(2, testee.number_of_accounts());
+
+        // the cap is inherited by trackers for child slots
+        let child = testee.new_from_parent_limits();
+        assert_eq!(2, child.max_writable_accounts_per_transaction);
+
+        // the default cap is the runtime's default account lock limit
+        assert_eq!(
+            solana_transaction::sanitized::MAX_TX_ACCOUNT_LOCKS,
+            DEFAULT_MAX_WRITABLE_ACCOUNTS_PER_TRANSACTION
+        );
+    }
+
+    #[test]
+    fn test_cost_tracker_reset_returns_stats() {
+        let acct1 = Pubkey::new_unique();
+        let acct2 = Pubkey::new_unique();
+        let mut testee = CostTracker::default();
+
+        let transaction1 = WritableKeysTransaction(vec![acct1, acct2]);
+        let transaction2 = WritableKeysTransaction(vec![acct2]);
+        assert!(testee
+            .try_add(&simple_transaction_cost(&transaction1, 5))
+            .is_ok());
+        assert!(testee
+            .try_add(&simple_transaction_cost(&transaction2, 7))
+            .is_ok());
+
+        assert_eq!(
+            testee.reset(),
+            CostTrackerStats {
+                block_cost: 12,
+                vote_cost: 0,
+                transaction_count: 2,
+                number_of_accounts: 2,
+                costliest_account: acct2,
+                costliest_account_cost: 12,
+            }
+        );
+
+        // the next block starts from empty
+        assert_eq!(testee.reset(), CostTrackerStats::default());
+        assert_eq!(0, testee.block_cost);
+        assert_eq!(0, testee.number_of_accounts());
+    }
+
     #[test]
     fn test_cost_tracker_remove() {
         let mint_keypair = test_setup();
@@ -726,6 +1146,55 @@ mod tests {
         assert!(testee.try_add(&tx_cost1).is_err());
     }
 
+    #[test]
+    fn test_cost_tracker_verify_consistency() {
+        let mut testee = CostTracker::default();
+        assert!(testee.verify_consistency());
+
+        // block cost equals sum of account costs for single account transactions
+        let tx1 = WritableKeysTransaction(vec![Pubkey::new_unique()]);
+        let tx_cost1 = simple_transaction_cost(&tx1, 5);
+        testee.add_transaction_cost(&tx_cost1);
+        assert!(testee.verify_consistency());
+
+        // multi-account transactions keep block cost within bounds
+        let tx2 = WritableKeysTransaction(vec![Pubkey::new_unique(), Pubkey::new_unique()]);
+        let tx_cost2 = simple_transaction_cost(&tx2, 7);
+        testee.add_transaction_cost(&tx_cost2);
+        assert!(testee.verify_consistency());
+        testee.remove_transaction_cost(&tx_cost2);
+        assert!(testee.verify_consistency());
+
+        // transactions without writable accounts only charge the block
+        let tx4 = WritableKeysTransaction(vec![]);
+        let tx_cost4 = simple_transaction_cost(&tx4, 11);
+        testee.add_transaction_cost(&tx_cost4);
+        assert!(testee.verify_consistency());
+        testee.remove_transaction_cost(&tx_cost4);
+        assert!(testee.verify_consistency());
+
+        // injected block cost drift is detected
+        testee.block_cost += 1;
+        assert!(!testee.verify_consistency());
+        testee.block_cost -= 2;
+        assert!(!testee.verify_consistency());
+        testee.block_cost += 1;
+        assert!(testee.verify_consistency());
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic]
+    fn test_cost_tracker_remove_never_added_transaction() {
+        let mut testee = CostTracker::default();
+        let tx1 = WritableKeysTransaction(vec![Pubkey::new_unique()]);
+        testee.add_transaction_cost(&simple_transaction_cost(&tx1, 5));
+
+        // removing a transaction that was never added trips the debug assertion
+        let tx2 = WritableKeysTransaction(vec![Pubkey::new_unique()]);
+        testee.remove_transaction_cost(&simple_transaction_cost(&tx2, 3));
+    }
+
     #[test]
     fn test_cost_tracker_try_add_is_atomic() {
         let acct1 = Pubkey::new_unique();
@@ -786,6 +1255,64 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_cost_tracker_try_add_batch() {
+        let acct1 = Pubkey::new_unique();
+        let acct2 = Pubkey::new_unique();
+        let acct3 = Pubkey::new_unique();
+        let cost = 100;
+        let account_max = cost * 2;
+        let block_max = account_max * 3;
+
+        let mut testee = CostTracker::new(account_max, block_max, block_max);
+        let tx0 = WritableKeysTransaction(vec![acct1]);
+        let tx_cost0 = simple_transaction_cost(&tx0, cost);
+        assert!(testee.try_add(&tx_cost0).is_ok());
+
+        // third transaction overflows acct1 when combined with earlier ones, nothing in the
+        // batch is committed
+        {
+            let tx1 = WritableKeysTransaction(vec![acct2, acct3]);
+            let tx2 = WritableKeysTransaction(vec![acct2]);
+            let tx3 = WritableKeysTransaction(vec![acct1, acct3]);
+            let batch = [
+                simple_transaction_cost(&tx1, cost),
+                simple_transaction_cost(&tx2, cost),
+                simple_transaction_cost(&tx3, cost * 2),
+            ];
+            assert_eq!(testee.try_add_batch(&batch), Err(2));
+            assert_eq!(cost, testee.block_cost);
+            assert_eq!(1, testee.transaction_count());
+            assert_eq!(1, testee.number_of_accounts());
+            assert_eq!(1, testee.cost_by_writable_accounts.len());
+            assert_eq!(vec![(acct1, cost)], testee.top_accounts(3));
+        }
+
+        // whole batch fits, including overlapping accounts within the batch
+        {
+            let tx1 = WritableKeysTransaction(vec![acct2, acct3]);
+            let tx2 = WritableKeysTransaction(vec![acct2]);
+            let tx3 = WritableKeysTransaction(vec![acct1, acct3]);
+            let batch = [
+                simple_transaction_cost(&tx1, cost),
+                simple_transaction_cost(&tx2, cost),
+                simple_transaction_cost(&tx3, cost),
+            ];
+            assert_eq!(testee.try_add_batch(&batch), Ok(()));
+            assert_eq!(cost * 4, testee.block_cost);
+            assert_eq!(4, testee.transaction_count());
+            assert_eq!(
+                vec![(acct1, cost * 2), (acct2, cost * 2), (acct3, cost * 2)]
+                    .into_iter()
+                    .collect::<HashMap<_, _>>(),
+                testee
+                    .top_accounts(3)
+                    .into_iter()
+                    .collect::<HashMap<_, _>>()
+            );
+        }
+    }
+
     #[test]
     fn test_adjust_transaction_execution_cost() {
         let acct1 = Pubkey::new_unique();
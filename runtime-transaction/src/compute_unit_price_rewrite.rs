@@ -0,0 +1,97 @@
+use {
+    solana_compute_budget_instruction::compute_budget_instruction_details::ComputeBudgetInstructionDetails,
+    solana_compute_budget_interface::ComputeBudgetInstruction,
+};
+
+/// Returns the index of transaction's `SetComputeUnitPrice` instruction and the encoded data of
+/// its replacement that sets `compute_unit_price`, so tooling can bump a transaction's price and
+/// re-serialize it without hand-encoding the instruction.
+/// Returns None if the transaction does not set compute unit price.
+pub fn compute_unit_price_replacement(
+    compute_budget_instruction_details: &ComputeBudgetInstructionDetails,
+    compute_unit_price: u64,
+) -> Option<(u8, Vec<u8>)> {
+    compute_budget_instruction_details
+        .requested_compute_unit_price()
+        .map(|(index, _requested_compute_unit_price)| {
+            (
+                index,
+                ComputeBudgetInstruction::set_compute_unit_price(compute_unit_price).data,
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_hash::Hash,
+        solana_instruction::Instruction,
+        solana_keypair::Keypair,
+        solana_message::{Message, SimpleAddressLoader},
+        solana_pubkey::Pubkey,
+        solana_reserved_account_keys::ReservedAccountKeys,
+        solana_signer::Signer,
+        solana_svm_transaction::svm_message::SVMMessage,
+        solana_transaction::{
+            sanitized::{MessageHash, SanitizedTransaction},
+            versioned::VersionedTransaction,
+            Transaction,
+        },
+    };
+
+    fn build_transaction(instructions: &[Instruction]) -> Transaction {
+        let payer_keypair = Keypair::new();
+        Transaction::new(
+            &[&payer_keypair],
+            Message::new(instructions, Some(&payer_keypair.pubkey())),
+            Hash::default(),
+        )
+    }
+
+    fn get_compute_budget_instruction_details(
+        transaction: &Transaction,
+    ) -> ComputeBudgetInstructionDetails {
+        let transaction = SanitizedTransaction::try_create(
+            VersionedTransaction::from(transaction.clone()),
+            MessageHash::Compute,
+            None,
+            SimpleAddressLoader::Disabled,
+            &ReservedAccountKeys::empty_key_set(),
+        )
+        .unwrap();
+        ComputeBudgetInstructionDetails::try_from(SVMMessage::program_instructions_iter(
+            &transaction,
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_compute_unit_price_replacement() {
+        let mut transaction = build_transaction(&[
+            Instruction::new_with_bincode(Pubkey::new_unique(), &0_u8, vec![]),
+            ComputeBudgetInstruction::set_compute_unit_limit(10_000),
+            ComputeBudgetInstruction::set_compute_unit_price(1_000),
+        ]);
+        let details = get_compute_budget_instruction_details(&transaction);
+        assert_eq!(details.requested_compute_unit_price(), Some((2, 1_000)));
+
+        let (index, data) = compute_unit_price_replacement(&details, 5_000).unwrap();
+        assert_eq!(index, 2);
+
+        // rewritten transaction decodes to the new price
+        transaction.message.instructions[index as usize].data = data;
+        let details = get_compute_budget_instruction_details(&transaction);
+        assert_eq!(details.requested_compute_unit_price(), Some((2, 5_000)));
+    }
+
+    #[test]
+    fn test_compute_unit_price_replacement_without_price() {
+        let transaction = build_transaction(&[
+            Instruction::new_with_bincode(Pubkey::new_unique(), &0_u8, vec![]),
+            ComputeBudgetInstruction::set_compute_unit_limit(10_000),
+        ]);
+        let details = get_compute_budget_instruction_details(&transaction);
+        assert_eq!(compute_unit_price_replacement(&details, 5_000), None);
+    }
+}
@@ -1,5 +1,6 @@
 #![allow(clippy::arithmetic_side_effects)]
 
+pub mod compute_unit_price_rewrite;
 pub mod runtime_transaction;
 pub mod signature_details;
 pub mod transaction_meta;
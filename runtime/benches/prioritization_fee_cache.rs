@@ -64,6 +64,55 @@ fn bench_process_transactions_single_slot(bencher: &mut Bencher) {
     });
 }
 
+// Writable accounts of transactions built by `build_sanitized_transaction()` fit inline, these
+// transactions write-lock more accounts than that to compare against spilled writable accounts.
+fn build_sanitized_transaction_with_many_writable_accounts(
+    compute_unit_price: u64,
+    signer_account: &Pubkey,
+    num_write_accounts: usize,
+) -> RuntimeTransaction<SanitizedTransaction> {
+    let transfer_lamports = 1;
+    let mut instructions: Vec<_> = (0..num_write_accounts)
+        .map(|_| {
+            system_instruction::transfer(signer_account, &Pubkey::new_unique(), transfer_lamports)
+        })
+        .collect();
+    instructions.push(ComputeBudgetInstruction::set_compute_unit_limit(
+        TRANSFER_TRANSACTION_COMPUTE_UNIT * num_write_accounts as u32,
+    ));
+    instructions.push(ComputeBudgetInstruction::set_compute_unit_price(
+        compute_unit_price,
+    ));
+    let transaction = Transaction::new_unsigned(Message::new(&instructions, Some(signer_account)));
+
+    RuntimeTransaction::from_transaction_for_tests(transaction)
+}
+
+#[bench]
+#[ignore]
+fn bench_process_transactions_single_slot_many_writable_accounts(bencher: &mut Bencher) {
+    const NUM_WRITE_ACCOUNTS: usize = 16;
+    let prioritization_fee_cache = PrioritizationFeeCache::default();
+
+    let bank = Arc::new(Bank::default_for_tests());
+
+    // build test transactions
+    let transactions: Vec<_> = (0..5000)
+        .map(|n| {
+            let compute_unit_price = n % 7;
+            build_sanitized_transaction_with_many_writable_accounts(
+                compute_unit_price,
+                &Pubkey::new_unique(),
+                NUM_WRITE_ACCOUNTS,
+            )
+        })
+        .collect();
+
+    bencher.iter(|| {
+        prioritization_fee_cache.update(&bank, transactions.iter());
+    });
+}
+
 fn process_transactions_multiple_slots(banks: &[Arc<Bank>], num_slots: usize, num_threads: usize) {
     let prioritization_fee_cache = Arc::new(PrioritizationFeeCache::default());
 
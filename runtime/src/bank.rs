@@ -4866,6 +4866,9 @@ impl Bank {
                 vote_cost_limit,
             );
         }
+        self.write_cost_tracker()
+            .unwrap()
+            .set_max_writable_accounts_per_transaction(self.get_transaction_account_lock_limit());
 
         if !debug_do_not_add_builtins {
             for builtin in BUILTINS
@@ -6521,6 +6524,14 @@ impl Bank {
                 vote_cost_limit,
             );
         }
+
+        if new_feature_activations.contains(&feature_set::increase_tx_account_lock_limit::id()) {
+            self.write_cost_tracker()
+                .unwrap()
+                .set_max_writable_accounts_per_transaction(
+                    self.get_transaction_account_lock_limit(),
+                );
+        }
     }
 
     fn apply_updated_hashes_per_tick(&mut self, hashes_per_tick: u64) {
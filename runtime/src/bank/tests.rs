@@ -8184,6 +8184,73 @@ fn test_block_limits() {
     );
 }
 
+#[test]
+fn test_max_writable_accounts_per_transaction() {
+    let (bank0, _bank_forks) = create_simple_test_arc_bank(100_000);
+    let mut bank = Bank::new_from_parent(bank0, &Pubkey::default(), 1);
+    assert!(!bank
+        .feature_set
+        .is_active(&feature_set::increase_tx_account_lock_limit::id()));
+    assert_eq!(
+        bank.read_cost_tracker()
+            .unwrap()
+            .get_max_writable_accounts_per_transaction(),
+        64,
+        "before activating the feature, cap should match the old lock limit"
+    );
+
+    // Activate `increase_tx_account_lock_limit` feature
+    bank.store_account(
+        &feature_set::increase_tx_account_lock_limit::id(),
+        &feature::create_account(&Feature::default(), 42),
+    );
+    bank.apply_feature_activations(ApplyFeatureActivationsCaller::NewFromParent, true);
+    assert_eq!(
+        bank.read_cost_tracker()
+            .unwrap()
+            .get_max_writable_accounts_per_transaction(),
+        MAX_TX_ACCOUNT_LOCKS,
+        "after activating the feature, cap should match the new lock limit"
+    );
+
+    // Make sure the cap propagates to the child-bank.
+    let bank = Bank::new_from_parent(Arc::new(bank), &Pubkey::default(), 2);
+    assert_eq!(
+        bank.read_cost_tracker()
+            .unwrap()
+            .get_max_writable_accounts_per_transaction(),
+        MAX_TX_ACCOUNT_LOCKS,
+        "child bank should have new cap"
+    );
+
+    // A lock limit overridden by the node applies to the cap as well.
+    let (genesis_config, _keypair) = create_genesis_config(100_000);
+    let transaction_account_lock_limit = MAX_TX_ACCOUNT_LOCKS * 2;
+    let bank = Bank::new_with_paths(
+        &genesis_config,
+        Arc::new(RuntimeConfig {
+            transaction_account_lock_limit: Some(transaction_account_lock_limit),
+            ..RuntimeConfig::default()
+        }),
+        Vec::default(),
+        None,
+        None,
+        false,
+        Some(ACCOUNTS_DB_CONFIG_FOR_TESTING),
+        None,
+        Some(Pubkey::new_unique()),
+        Arc::new(AtomicBool::new(false)),
+        None,
+        None,
+    );
+    assert_eq!(
+        bank.read_cost_tracker()
+            .unwrap()
+            .get_max_writable_accounts_per_transaction(),
+        transaction_account_lock_limit,
+    );
+}
+
 #[test]
 fn test_program_replacement() {
     let mut bank = create_simple_test_bank(0);
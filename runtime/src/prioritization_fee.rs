@@ -1,7 +1,7 @@
 use {
     solana_measure::measure_us,
     solana_sdk::{clock::Slot, pubkey::Pubkey, saturating_add_assign},
-    std::collections::HashMap,
+    std::{collections::HashMap, time::Instant},
 };
 
 #[derive(Debug, Default)]
@@ -148,6 +148,12 @@ pub struct PrioritizationFee {
     // are finalized, and can be made available for use (e.g., RPC query)
     is_finalized: bool,
 
+    // When the first update for this block was received, used to expire stale blocks.
+    first_update_time: Instant,
+
+    // Total prioritization fees, in lamports, paid by transactions in this block.
+    total_priority_fee: u64,
+
     // slot prioritization fee metrics
     metrics: PrioritizationFeeMetrics,
 }
@@ -158,14 +164,39 @@ impl Default for PrioritizationFee {
             min_transaction_fee: u64::MAX,
             min_writable_account_fees: HashMap::new(),
             is_finalized: false,
+            first_update_time: Instant::now(),
+            total_priority_fee: 0,
             metrics: PrioritizationFeeMetrics::default(),
         }
     }
 }
 
 impl PrioritizationFee {
+    /// Create a finalized block prioritization fee from fees collected elsewhere, such as
+    /// imported from a peer's cache.
+    pub fn new_finalized(
+        min_transaction_fee: Option<u64>,
+        min_writable_account_fees: HashMap<Pubkey, u64>,
+    ) -> Self {
+        PrioritizationFee {
+            min_transaction_fee: min_transaction_fee.unwrap_or(u64::MAX),
+            min_writable_account_fees,
+            is_finalized: true,
+            first_update_time: Instant::now(),
+            total_priority_fee: 0,
+            metrics: PrioritizationFeeMetrics::default(),
+        }
+    }
+
     /// Update self for minimum transaction fee in the block and minimum fee for each writable account.
-    pub fn update(&mut self, transaction_fee: u64, writable_accounts: Vec<Pubkey>) {
+    /// `transaction_fee` is the transaction's compute unit price, `priority_fee` the total
+    /// prioritization fee in lamports it pays.
+    pub fn update(
+        &mut self,
+        transaction_fee: u64,
+        priority_fee: u64,
+        writable_accounts: impl IntoIterator<Item = Pubkey>,
+    ) {
         let (_, update_us) = measure_us!({
             if !self.is_finalized {
                 if transaction_fee < self.min_transaction_fee {
@@ -181,9 +212,7 @@ impl PrioritizationFee {
                         .or_insert(transaction_fee);
                 }
 
-                self.metrics
-                    .accumulate_total_prioritization_fee(transaction_fee);
-                self.metrics.update_prioritization_fee(transaction_fee);
+                self.accumulate_totals(transaction_fee, priority_fee);
             } else {
                 self.metrics
                     .increment_attempted_update_on_finalized_fee_count(1);
@@ -193,6 +222,24 @@ impl PrioritizationFee {
         self.metrics.accumulate_total_update_elapsed_us(update_us);
     }
 
+    /// Update self for a transaction that is excluded from minimum fees, it only contributes to
+    /// the block's totals and transaction counts.
+    pub fn update_totals(&mut self, transaction_fee: u64, priority_fee: u64) {
+        if !self.is_finalized {
+            self.accumulate_totals(transaction_fee, priority_fee);
+        } else {
+            self.metrics
+                .increment_attempted_update_on_finalized_fee_count(1);
+        }
+    }
+
+    fn accumulate_totals(&mut self, transaction_fee: u64, priority_fee: u64) {
+        saturating_add_assign!(self.total_priority_fee, priority_fee);
+        self.metrics
+            .accumulate_total_prioritization_fee(transaction_fee);
+        self.metrics.update_prioritization_fee(transaction_fee);
+    }
+
     /// Accounts that have minimum fees lesser or equal to the minimum fee in the block are redundant, they are
     /// removed to reduce memory footprint when mark_block_completed() is called.
     fn prune_irrelevant_writable_accounts(&mut self) {
@@ -215,6 +262,20 @@ impl PrioritizationFee {
         (self.min_transaction_fee != u64::MAX).then_some(self.min_transaction_fee)
     }
 
+    /// Total prioritization fees, in lamports, of transactions that landed in this block.
+    pub fn get_total_priority_fee(&self) -> u64 {
+        self.total_priority_fee
+    }
+
+    /// Fraction of transactions in this block that paid zero prioritization fee, or None if no
+    /// transaction was recorded.
+    pub fn get_zero_fee_transaction_ratio(&self) -> Option<f64> {
+        let zero_fee_count = self.metrics.non_prioritized_transactions_count;
+        let total_count =
+            zero_fee_count.saturating_add(self.metrics.prioritized_transactions_count);
+        (total_count > 0).then(|| zero_fee_count as f64 / total_count as f64)
+    }
+
     pub fn get_writable_account_fee(&self, key: &Pubkey) -> Option<u64> {
         self.min_writable_account_fees.get(key).copied()
     }
@@ -231,6 +292,10 @@ impl PrioritizationFee {
         self.is_finalized
     }
 
+    pub fn first_update_time(&self) -> Instant {
+        self.first_update_time
+    }
+
     pub fn report_metrics(&self, slot: Slot) {
         self.metrics.report(slot);
 
@@ -274,7 +339,7 @@ mod tests {
         // -----------------------------------------------------------------------
         // [5,   a, b             ]  -->  [5,     5,         5,         nil      ]
         {
-            prioritization_fee.update(5, vec![write_account_a, write_account_b]);
+            prioritization_fee.update(5, 500, vec![write_account_a, write_account_b]);
             assert_eq!(5, prioritization_fee.get_min_transaction_fee().unwrap());
             assert_eq!(
                 5,
@@ -298,7 +363,7 @@ mod tests {
         // -----------------------------------------------------------------------
         // [9,      b, c          ]  -->  [5,     5,         5,         9        ]
         {
-            prioritization_fee.update(9, vec![write_account_b, write_account_c]);
+            prioritization_fee.update(9, 900, vec![write_account_b, write_account_c]);
             assert_eq!(5, prioritization_fee.get_min_transaction_fee().unwrap());
             assert_eq!(
                 5,
@@ -325,7 +390,7 @@ mod tests {
         // -----------------------------------------------------------------------
         // [2,   a,    c          ]  -->  [2,     2,         5,         2        ]
         {
-            prioritization_fee.update(2, vec![write_account_a, write_account_c]);
+            prioritization_fee.update(2, 200, vec![write_account_a, write_account_c]);
             assert_eq!(2, prioritization_fee.get_min_transaction_fee().unwrap());
             assert_eq!(
                 2,
@@ -347,6 +412,15 @@ mod tests {
             );
         }
 
+        assert_eq!(1600, prioritization_fee.get_total_priority_fee());
+
+        // a transaction excluded from minimum fees only adds to totals
+        {
+            prioritization_fee.update_totals(1, 100);
+            assert_eq!(2, prioritization_fee.get_min_transaction_fee().unwrap());
+            assert_eq!(1700, prioritization_fee.get_total_priority_fee());
+        }
+
         // assert after prune, account a and c should be removed from cache to save space
         {
             prioritization_fee.prune_irrelevant_writable_accounts();
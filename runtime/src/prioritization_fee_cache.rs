@@ -1,22 +1,27 @@
 use {
     crate::{bank::Bank, prioritization_fee::*},
-    crossbeam_channel::{unbounded, Receiver, Sender, TryRecvError},
+    crossbeam_channel::{unbounded, Receiver, SendError, Sender, TryRecvError},
     log::*,
+    serde_derive::{Deserialize, Serialize},
+    smallvec::SmallVec,
     solana_accounts_db::account_locks::validate_account_locks,
+    solana_compute_budget::compute_budget_limits::{break_even_cu_price, ComputeBudgetLimits},
     solana_measure::measure_us,
     solana_runtime_transaction::transaction_with_meta::TransactionWithMeta,
     solana_sdk::{
         clock::{BankId, Slot},
+        fee::FeeBudgetLimits,
         pubkey::Pubkey,
     },
     std::{
-        collections::{BTreeMap, HashMap},
+        collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
+        io::{self, Read, Write},
         sync::{
             atomic::{AtomicU64, Ordering},
-            Arc, RwLock,
+            Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard,
         },
         thread::{sleep, Builder, JoinHandle},
-        time::Duration,
+        time::{Duration, Instant},
     },
 };
 
@@ -25,11 +30,24 @@ use {
 /// land a transaction in the current block.
 const MAX_NUM_RECENT_BLOCKS: u64 = 150;
 
+/// Most transactions write-lock only a few accounts, such as fee payer and transfer recipient,
+/// their writable accounts are kept inline to avoid a heap allocation per transaction update.
+const INLINE_WRITABLE_ACCOUNTS: usize = 4;
+
+type WritableAccounts = SmallVec<[Pubkey; INLINE_WRITABLE_ACCOUNTS]>;
+
 /// Thers is no guarantee that slots coming in order, we keep extra slots in the buffer.
 const MAX_UNFINALIZED_SLOTS: u64 = 128;
 
+/// Percentile of available blocks' fees that `recommend_fee_for_transaction()` recommends.
+const RECOMMENDED_FEE_PERCENTILE: u8 = 75;
+
 type UnfinalizedPrioritizationFees = BTreeMap<Slot, HashMap<BankId, PrioritizationFee>>;
 
+/// Slots of `UnfinalizedPrioritizationFees`, kept in sync by whoever owns the unfinalized fees so
+/// queries can see them.
+type UnfinalizedSlots = RwLock<BTreeSet<Slot>>;
+
 #[derive(Debug, Default)]
 struct PrioritizationFeeCacheMetrics {
     // Count of transactions that successfully updated each slot's prioritization fee cache.
@@ -129,66 +147,253 @@ enum CacheServiceUpdate {
         slot: Slot,
         bank_id: BankId,
         transaction_fee: u64,
-        writable_accounts: Vec<Pubkey>,
+        priority_fee: u64,
+        // false if the transaction only counts towards the block's totals, not minimum fees
+        count_in_minimums: bool,
+        writable_accounts: WritableAccounts,
     },
     BankFinalized {
         slot: Slot,
         bank_id: BankId,
     },
+    SlotAbandoned {
+        slot: Slot,
+    },
     Exit,
 }
 
+/// Finalized prioritization fees of a block, as exported by `PrioritizationFeeCache::snapshot()`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlotPrioritizationFeeSnapshot {
+    pub min_transaction_fee: Option<u64>,
+    pub min_writable_account_fees: HashMap<Pubkey, u64>,
+}
+
+/// Finalized prioritization fees of recent blocks exported from a `PrioritizationFeeCache`, it can
+/// be imported into another cache to bootstrap its fee estimates.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PrioritizationFeeSnapshot {
+    pub slots: BTreeMap<Slot, SlotPrioritizationFeeSnapshot>,
+}
+
+/// Distribution of writable accounts' minimum prioritization fees across all available blocks,
+/// as returned by `PrioritizationFeeCache::account_fee_stats()`. Each account contributes its
+/// lowest minimum fee among the blocks it was written in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AccountFeeStats {
+    pub distinct_accounts: usize,
+    pub min: u64,
+    pub max: u64,
+    pub median: u64,
+}
+
+/// How updates to a `PrioritizationFeeCache` are applied.
+#[derive(Debug)]
+enum CacheUpdateMode {
+    /// Updates are sent to `service_thread`, which applies them in the background.
+    Service {
+        service_thread: Option<JoinHandle<()>>,
+        sender: Sender<CacheServiceUpdate>,
+    },
+    /// Updates are applied inline by the caller.
+    Synchronous {
+        unfinalized: Mutex<UnfinalizedPrioritizationFees>,
+        max_slot_age: Option<Duration>,
+    },
+}
+
+/// Options of a `PrioritizationFeeCache` created with `new_with_config()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrioritizationFeeCacheConfig {
+    /// Maximum number of finalized blocks kept in the cache.
+    pub capacity: u64,
+    /// If set, blocks whose first update is older than this are evicted whenever a slot is
+    /// finalized. This keeps a node that stalled and resumed from serving estimates based on
+    /// stale blocks.
+    pub max_slot_age: Option<Duration>,
+    /// If set, a slot is finalized this long after `finalize_priority_fee()` is called for it.
+    /// Transaction updates of the slot that arrive late, within the grace period, are still
+    /// accounted for. Requires the service thread, so it cannot be combined with `synchronous`.
+    pub finalize_grace_period: Option<Duration>,
+    /// Transactions whose compute unit price is below this are excluded from block and account
+    /// minimum fees, so zero or dust priority transactions don't drag them down to zero. They
+    /// still count towards the block's totals.
+    pub min_countable_fee: u64,
+    /// If set, updates, finalizations and abandonments are applied inline on the calling thread
+    /// instead of on a background service thread, so their effects are visible to queries as
+    /// soon as the call returns. Intended for tests and minimal embeddings.
+    pub synchronous: bool,
+}
+
+impl Default for PrioritizationFeeCacheConfig {
+    fn default() -> Self {
+        Self {
+            capacity: MAX_NUM_RECENT_BLOCKS,
+            max_slot_age: None,
+            finalize_grace_period: None,
+            min_countable_fee: 0,
+            synchronous: false,
+        }
+    }
+}
+
 /// Stores up to MAX_NUM_RECENT_BLOCKS recent block's prioritization fee,
 /// A separate internal thread `service_thread` handles additional tasks when a bank is frozen,
-/// and collecting stats and reporting metrics.
+/// and collecting stats and reporting metrics, unless the cache is synchronous.
 #[derive(Debug)]
 pub struct PrioritizationFeeCache {
     cache: Arc<RwLock<BTreeMap<Slot, PrioritizationFee>>>,
-    service_thread: Option<JoinHandle<()>>,
-    sender: Sender<CacheServiceUpdate>,
+    unfinalized_slots: Arc<UnfinalizedSlots>,
+    mode: CacheUpdateMode,
     metrics: Arc<PrioritizationFeeCacheMetrics>,
+    cache_max_size: usize,
+    // transactions with compute unit price below this are excluded from minimum fees
+    min_countable_fee: u64,
 }
 
 impl Default for PrioritizationFeeCache {
     fn default() -> Self {
-        Self::new(MAX_NUM_RECENT_BLOCKS)
+        Self::new_with_config(PrioritizationFeeCacheConfig::default())
     }
 }
 
 impl Drop for PrioritizationFeeCache {
     fn drop(&mut self) {
-        let _ = self.sender.send(CacheServiceUpdate::Exit);
-        self.service_thread
-            .take()
-            .unwrap()
-            .join()
-            .expect("Prioritization fee cache servicing thread failed to join");
+        if let CacheUpdateMode::Service {
+            service_thread,
+            sender,
+        } = &mut self.mode
+        {
+            let _ = sender.send(CacheServiceUpdate::Exit);
+            service_thread
+                .take()
+                .unwrap()
+                .join()
+                .expect("Prioritization fee cache servicing thread failed to join");
+        }
     }
 }
 
 impl PrioritizationFeeCache {
     pub fn new(capacity: u64) -> Self {
+        Self::new_with_config(PrioritizationFeeCacheConfig {
+            capacity,
+            ..PrioritizationFeeCacheConfig::default()
+        })
+    }
+
+    pub fn new_with_config(config: PrioritizationFeeCacheConfig) -> Self {
+        let PrioritizationFeeCacheConfig {
+            capacity,
+            max_slot_age,
+            finalize_grace_period,
+            min_countable_fee,
+            synchronous,
+        } = config;
+        assert!(
+            !(synchronous && finalize_grace_period.is_some()),
+            "finalize grace period requires the service thread"
+        );
         let cache = Arc::new(RwLock::new(BTreeMap::new()));
-        let (sender, receiver) = unbounded();
+        let unfinalized_slots = Arc::new(UnfinalizedSlots::default());
         let metrics = Arc::new(PrioritizationFeeCacheMetrics::default());
 
-        let service_thread = Some(
-            Builder::new()
-                .name("solPrFeeCachSvc".to_string())
-                .spawn({
-                    let cache = cache.clone();
-                    let metrics = metrics.clone();
-                    move || Self::service_loop(cache, capacity as usize, receiver, metrics)
-                })
-                .unwrap(),
-        );
+        let mode = if synchronous {
+            CacheUpdateMode::Synchronous {
+                unfinalized: Mutex::new(UnfinalizedPrioritizationFees::new()),
+                max_slot_age,
+            }
+        } else {
+            let (sender, receiver) = unbounded();
+            let service_thread = Some(
+                Builder::new()
+                    .name("solPrFeeCachSvc".to_string())
+                    .spawn({
+                        let cache = cache.clone();
+                        let unfinalized_slots = unfinalized_slots.clone();
+                        let metrics = metrics.clone();
+                        move || {
+                            Self::service_loop(
+                                cache,
+                                unfinalized_slots,
+                                capacity as usize,
+                                max_slot_age,
+                                finalize_grace_period,
+                                receiver,
+                                metrics,
+                            )
+                        }
+                    })
+                    .unwrap(),
+            );
+            CacheUpdateMode::Service {
+                service_thread,
+                sender,
+            }
+        };
 
         PrioritizationFeeCache {
             cache,
-            service_thread,
-            sender,
+            unfinalized_slots,
+            mode,
             metrics,
+            cache_max_size: capacity as usize,
+            min_countable_fee,
+        }
+    }
+
+    /// Sends `update` to the service thread, or applies it immediately if the cache is
+    /// synchronous.
+    fn send_update(&self, update: CacheServiceUpdate) -> Result<(), SendError<CacheServiceUpdate>> {
+        let (unfinalized, max_slot_age) = match &self.mode {
+            CacheUpdateMode::Service { sender, .. } => return sender.send(update),
+            CacheUpdateMode::Synchronous {
+                unfinalized,
+                max_slot_age,
+            } => (unfinalized, *max_slot_age),
+        };
+        let mut unfinalized = lock(unfinalized);
+        match update {
+            CacheServiceUpdate::TransactionUpdate {
+                slot,
+                bank_id,
+                transaction_fee,
+                priority_fee,
+                count_in_minimums,
+                writable_accounts,
+            } => Self::update_cache(
+                &mut unfinalized,
+                &self.unfinalized_slots,
+                slot,
+                bank_id,
+                transaction_fee,
+                priority_fee,
+                count_in_minimums,
+                writable_accounts,
+                &self.metrics,
+            ),
+            CacheServiceUpdate::BankFinalized { slot, bank_id } => {
+                Self::finalize_slot(
+                    &mut unfinalized,
+                    &self.unfinalized_slots,
+                    &self.cache,
+                    self.cache_max_size,
+                    max_slot_age,
+                    Instant::now(),
+                    slot,
+                    bank_id,
+                    &self.metrics,
+                );
+                self.metrics.report(slot);
+            }
+            CacheServiceUpdate::SlotAbandoned { slot } => {
+                unfinalized.remove(&slot);
+                write_lock(&self.unfinalized_slots).remove(&slot);
+                write_lock(&self.cache).remove(&slot);
+            }
+            CacheServiceUpdate::Exit => {}
         }
+        Ok(())
     }
 
     /// Update with a list of non-vote transactions' compute_budget_details and account_locks; Only
@@ -227,27 +432,36 @@ impl PrioritizationFeeCache {
                     continue;
                 }
 
-                let writable_accounts = sanitized_transaction
-                    .account_keys()
-                    .iter()
-                    .enumerate()
-                    .filter(|(index, _)| sanitized_transaction.is_writable(*index))
-                    .map(|(_, key)| *key)
-                    .collect();
-
-                self.sender
-                    .send(CacheServiceUpdate::TransactionUpdate {
-                        slot: bank.slot(),
-                        bank_id: bank.bank_id(),
-                        transaction_fee: compute_budget_limits.compute_unit_price,
-                        writable_accounts,
-                    })
-                    .unwrap_or_else(|err| {
-                        warn!(
-                            "prioritization fee cache transaction updates failed: {:?}",
-                            err
-                        );
-                    });
+                // transactions paying below the minimum countable fee still add to the block's
+                // totals, but don't drag minimum fees down.
+                let count_in_minimums =
+                    compute_budget_limits.compute_unit_price >= self.min_countable_fee;
+                let writable_accounts = if count_in_minimums {
+                    sanitized_transaction
+                        .account_keys()
+                        .iter()
+                        .enumerate()
+                        .filter(|(index, _)| sanitized_transaction.is_writable(*index))
+                        .map(|(_, key)| *key)
+                        .collect()
+                } else {
+                    WritableAccounts::new()
+                };
+
+                self.send_update(CacheServiceUpdate::TransactionUpdate {
+                    slot: bank.slot(),
+                    bank_id: bank.bank_id(),
+                    transaction_fee: compute_budget_limits.compute_unit_price,
+                    priority_fee: FeeBudgetLimits::from(compute_budget_limits).prioritization_fee,
+                    count_in_minimums,
+                    writable_accounts,
+                })
+                .unwrap_or_else(|err| {
+                    warn!(
+                        "prioritization fee cache transaction updates failed: {:?}",
+                        err
+                    );
+                });
             }
         });
 
@@ -258,8 +472,7 @@ impl PrioritizationFeeCache {
     /// Finalize prioritization fee when it's bank is completely replayed from blockstore,
     /// by pruning irrelevant accounts to save space, and marking its availability for queries.
     pub fn finalize_priority_fee(&self, slot: Slot, bank_id: BankId) {
-        self.sender
-            .send(CacheServiceUpdate::BankFinalized { slot, bank_id })
+        self.send_update(CacheServiceUpdate::BankFinalized { slot, bank_id })
             .unwrap_or_else(|err| {
                 warn!(
                     "prioritization fee cache signalling bank frozen failed: {:?}",
@@ -268,29 +481,59 @@ impl PrioritizationFeeCache {
             });
     }
 
+    /// Discard a slot's prioritization fee when its fork is abandoned, regardless of whether it
+    /// has been finalized, so its data won't pollute estimates if the slot number is reused.
+    pub fn abandon_slot(&self, slot: Slot) {
+        write_lock(&self.cache).remove(&slot);
+        // unfinalized data is owned by the service thread, also have it discard any update
+        // that is still in flight for this slot.
+        self.send_update(CacheServiceUpdate::SlotAbandoned { slot })
+            .unwrap_or_else(|err| {
+                warn!(
+                    "prioritization fee cache signalling slot abandoned failed: {:?}",
+                    err
+                )
+            });
+    }
+
     /// Internal function is invoked by worker thread to update slot's minimum prioritization fee.
     fn update_cache(
         unfinalized: &mut UnfinalizedPrioritizationFees,
+        unfinalized_slots: &UnfinalizedSlots,
         slot: Slot,
         bank_id: BankId,
         transaction_fee: u64,
-        writable_accounts: Vec<Pubkey>,
+        priority_fee: u64,
+        count_in_minimums: bool,
+        writable_accounts: WritableAccounts,
         metrics: &PrioritizationFeeCacheMetrics,
     ) {
-        let (_, entry_update_us) = measure_us!(unfinalized
-            .entry(slot)
-            .or_default()
-            .entry(bank_id)
-            .or_default()
-            .update(transaction_fee, writable_accounts));
+        let (_, entry_update_us) = measure_us!({
+            if !unfinalized.contains_key(&slot) {
+                write_lock(unfinalized_slots).insert(slot);
+            }
+            let prioritization_fee = unfinalized
+                .entry(slot)
+                .or_default()
+                .entry(bank_id)
+                .or_default();
+            if count_in_minimums {
+                prioritization_fee.update(transaction_fee, priority_fee, writable_accounts);
+            } else {
+                prioritization_fee.update_totals(transaction_fee, priority_fee);
+            }
+        });
         metrics.accumulate_total_entry_update_elapsed_us(entry_update_us);
         metrics.accumulate_successful_transaction_update_count(1);
     }
 
     fn finalize_slot(
         unfinalized: &mut UnfinalizedPrioritizationFees,
+        unfinalized_slots: &UnfinalizedSlots,
         cache: &RwLock<BTreeMap<Slot, PrioritizationFee>>,
         cache_max_size: usize,
+        max_slot_age: Option<Duration>,
+        now: Instant,
         slot: Slot,
         bank_id: BankId,
         metrics: &PrioritizationFeeCacheMetrics,
@@ -304,8 +547,14 @@ impl PrioritizationFeeCache {
         // block minimum fee.
         let (slot_prioritization_fee, slot_finalize_us) = measure_us!({
             // remove unfinalized slots
-            *unfinalized =
-                unfinalized.split_off(&slot.checked_sub(MAX_UNFINALIZED_SLOTS).unwrap_or_default());
+            let oldest_unfinalized_slot =
+                slot.checked_sub(MAX_UNFINALIZED_SLOTS).unwrap_or_default();
+            *unfinalized = unfinalized.split_off(&oldest_unfinalized_slot);
+            {
+                let mut unfinalized_slots = write_lock(unfinalized_slots);
+                *unfinalized_slots = unfinalized_slots.split_off(&oldest_unfinalized_slot);
+                unfinalized_slots.remove(&slot);
+            }
 
             let Some(mut slot_prioritization_fee) = unfinalized.remove(&slot) else {
                 return;
@@ -340,7 +589,13 @@ impl PrioritizationFeeCache {
         // Create new cache entry
         if let Some(slot_prioritization_fee) = slot_prioritization_fee {
             let (_, cache_lock_us) = measure_us!({
-                let mut cache = cache.write().unwrap();
+                let mut cache = write_lock(cache);
+                if let Some(max_slot_age) = max_slot_age {
+                    cache.retain(|_, prioritization_fee| {
+                        now.saturating_duration_since(prioritization_fee.first_update_time())
+                            <= max_slot_age
+                    });
+                }
                 while cache.len() >= cache_max_size {
                     cache.pop_first();
                 }
@@ -352,15 +607,44 @@ impl PrioritizationFeeCache {
 
     fn service_loop(
         cache: Arc<RwLock<BTreeMap<Slot, PrioritizationFee>>>,
+        unfinalized_slots: Arc<UnfinalizedSlots>,
         cache_max_size: usize,
+        max_slot_age: Option<Duration>,
+        finalize_grace_period: Option<Duration>,
         receiver: Receiver<CacheServiceUpdate>,
         metrics: Arc<PrioritizationFeeCacheMetrics>,
     ) {
         // Potentially there are more than one bank that updates Prioritization Fee
         // for a slot. The updates are tracked and finalized by bank_id.
         let mut unfinalized = UnfinalizedPrioritizationFees::new();
+        // Finalizations delayed by grace period, ordered by their due time.
+        let mut pending_finalizations: VecDeque<(Instant, Slot, BankId)> = VecDeque::new();
+
+        let finalize = |unfinalized: &mut UnfinalizedPrioritizationFees, slot, bank_id| {
+            Self::finalize_slot(
+                unfinalized,
+                &unfinalized_slots,
+                &cache,
+                cache_max_size,
+                max_slot_age,
+                Instant::now(),
+                slot,
+                bank_id,
+                &metrics,
+            );
+            metrics.report(slot);
+        };
 
         loop {
+            while let Some((_, slot, bank_id)) = pending_finalizations
+                .front()
+                .filter(|(due_time, _, _)| *due_time <= Instant::now())
+                .copied()
+            {
+                pending_finalizations.pop_front();
+                finalize(&mut unfinalized, slot, bank_id);
+            }
+
             let update = match receiver.try_recv() {
                 Ok(update) => update,
                 Err(TryRecvError::Empty) => {
@@ -377,25 +661,34 @@ impl PrioritizationFeeCache {
                     slot,
                     bank_id,
                     transaction_fee,
+                    priority_fee,
+                    count_in_minimums,
                     writable_accounts,
                 } => Self::update_cache(
                     &mut unfinalized,
+                    &unfinalized_slots,
                     slot,
                     bank_id,
                     transaction_fee,
+                    priority_fee,
+                    count_in_minimums,
                     writable_accounts,
                     &metrics,
                 ),
                 CacheServiceUpdate::BankFinalized { slot, bank_id } => {
-                    Self::finalize_slot(
-                        &mut unfinalized,
-                        &cache,
-                        cache_max_size,
-                        slot,
-                        bank_id,
-                        &metrics,
-                    );
-                    metrics.report(slot);
+                    match finalize_grace_period {
+                        Some(grace_period) => pending_finalizations.push_back((
+                            Instant::now() + grace_period,
+                            slot,
+                            bank_id,
+                        )),
+                        None => finalize(&mut unfinalized, slot, bank_id),
+                    }
+                }
+                CacheServiceUpdate::SlotAbandoned { slot } => {
+                    unfinalized.remove(&slot);
+                    write_lock(&unfinalized_slots).remove(&slot);
+                    write_lock(&cache).remove(&slot);
                 }
                 CacheServiceUpdate::Exit => {
                     break;
@@ -406,13 +699,49 @@ impl PrioritizationFeeCache {
 
     /// Returns number of blocks that have finalized minimum fees collection
     pub fn available_block_count(&self) -> usize {
-        self.cache.read().unwrap().len()
+        read_lock(&self.cache).len()
+    }
+
+    /// Returns the number of finalized blocks that retained a fee entry for `account`.
+    pub fn account_fee_sample_count(&self, account: &Pubkey) -> usize {
+        read_lock(&self.cache)
+            .values()
+            .filter(|slot_prioritization_fee| {
+                slot_prioritization_fee
+                    .get_writable_account_fee(account)
+                    .is_some()
+            })
+            .count()
+    }
+
+    /// Returns the oldest and newest slots with fees in the cache, finalized or not, or None if
+    /// the cache is empty.
+    pub fn covered_slot_range(&self) -> Option<(Slot, Slot)> {
+        let finalized_slot_range = self.finalized_slot_range();
+        let unfinalized_slots = read_lock(&self.unfinalized_slots);
+        let unfinalized_slot_range = unfinalized_slots
+            .first()
+            .zip(unfinalized_slots.last())
+            .map(|(min_slot, max_slot)| (*min_slot, *max_slot));
+        match (finalized_slot_range, unfinalized_slot_range) {
+            (Some((min_a, max_a)), Some((min_b, max_b))) => {
+                Some((min_a.min(min_b), max_a.max(max_b)))
+            }
+            (range, None) | (None, range) => range,
+        }
+    }
+
+    /// Returns the oldest and newest slots with finalized fees in the cache, or None if no slot
+    /// has been finalized. Only finalized slots are used for fee estimates.
+    pub fn finalized_slot_range(&self) -> Option<(Slot, Slot)> {
+        let cache = read_lock(&self.cache);
+        let (min_slot, _) = cache.first_key_value()?;
+        let (max_slot, _) = cache.last_key_value()?;
+        Some((*min_slot, *max_slot))
     }
 
     pub fn get_prioritization_fees(&self, account_keys: &[Pubkey]) -> Vec<(Slot, u64)> {
-        self.cache
-            .read()
-            .unwrap()
+        read_lock(&self.cache)
             .iter()
             .map(|(slot, slot_prioritization_fee)| {
                 let mut fee = slot_prioritization_fee
@@ -429,6 +758,258 @@ impl PrioritizationFeeCache {
             })
             .collect()
     }
+
+    /// Returns the minimum prioritization fee of each available block, in slot
+    /// order. The result is a point-in-time snapshot taken under the cache
+    /// lock, which is released before returning; it is not updated as blocks
+    /// are finalized or evicted afterwards.
+    pub fn get_prioritization_fees_owned(&self) -> Arc<Vec<u64>> {
+        let fees = read_lock(&self.cache)
+            .values()
+            .map(|slot_prioritization_fee| {
+                slot_prioritization_fee
+                    .get_min_transaction_fee()
+                    .unwrap_or_default()
+            })
+            .collect();
+        Arc::new(fees)
+    }
+
+    /// Returns statistics of writable accounts' minimum fees over all available blocks, all
+    /// fields are zero if no account fee is cached. Account fees are copied out under the
+    /// cache lock, which is released before the statistics are computed.
+    pub fn account_fee_stats(&self) -> AccountFeeStats {
+        let account_fees: Vec<_> = read_lock(&self.cache)
+            .values()
+            .flat_map(|slot_prioritization_fee| {
+                slot_prioritization_fee
+                    .get_writable_account_fees()
+                    .map(|(account, fee)| (*account, *fee))
+            })
+            .collect();
+
+        let mut min_fee_by_account = HashMap::<Pubkey, u64>::new();
+        for (account, fee) in account_fees {
+            min_fee_by_account
+                .entry(account)
+                .and_modify(|min_fee| *min_fee = (*min_fee).min(fee))
+                .or_insert(fee);
+        }
+        let mut fees: Vec<_> = min_fee_by_account.into_values().collect();
+        if fees.is_empty() {
+            return AccountFeeStats::default();
+        }
+        fees.sort_unstable();
+
+        AccountFeeStats {
+            distinct_accounts: fees.len(),
+            min: fees[0],
+            max: fees[fees.len() - 1],
+            median: fees[(fees.len() - 1) / 2],
+        }
+    }
+
+    /// Exports finalized prioritization fees of all available blocks.
+    pub fn snapshot(&self) -> PrioritizationFeeSnapshot {
+        let slots = read_lock(&self.cache)
+            .iter()
+            .map(|(slot, slot_prioritization_fee)| {
+                (
+                    *slot,
+                    SlotPrioritizationFeeSnapshot {
+                        min_transaction_fee: slot_prioritization_fee.get_min_transaction_fee(),
+                        min_writable_account_fees: slot_prioritization_fee
+                            .get_writable_account_fees()
+                            .map(|(account, fee)| (*account, *fee))
+                            .collect(),
+                    },
+                )
+            })
+            .collect();
+        PrioritizationFeeSnapshot { slots }
+    }
+
+    /// Imports finalized prioritization fees exported from another cache. Slots already present
+    /// in this cache are skipped; when cache is full, the oldest slots are evicted.
+    pub fn import_snapshot(&self, snapshot: PrioritizationFeeSnapshot) {
+        let mut cache = write_lock(&self.cache);
+        for (slot, slot_snapshot) in snapshot.slots {
+            cache.entry(slot).or_insert_with(|| {
+                PrioritizationFee::new_finalized(
+                    slot_snapshot.min_transaction_fee,
+                    slot_snapshot.min_writable_account_fees,
+                )
+            });
+        }
+        while cache.len() > self.cache_max_size {
+            cache.pop_first();
+        }
+    }
+
+    /// Serializes finalized prioritization fees of all available blocks to `writer`, they can
+    /// be loaded back with `read_from()`.
+    pub fn write_to(&self, writer: &mut dyn Write) -> io::Result<()> {
+        bincode::serialize_into(writer, &self.snapshot())
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))
+    }
+
+    /// Imports prioritization fees previously serialized by `write_to()`, with the same
+    /// semantics as `import_snapshot()`.
+    pub fn read_from(&self, reader: &mut dyn Read) -> io::Result<()> {
+        let snapshot: PrioritizationFeeSnapshot = bincode::deserialize_from(reader)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        self.import_snapshot(snapshot);
+        Ok(())
+    }
+
+    /// Returns total prioritization fees, in lamports, of transactions included in a finalized
+    /// `slot`, or None if the slot is not available.
+    pub fn slot_total_priority_fee(&self, slot: Slot) -> Option<u64> {
+        read_lock(&self.cache)
+            .get(&slot)
+            .map(|slot_prioritization_fee| slot_prioritization_fee.get_total_priority_fee())
+    }
+
+    /// Returns, for each finalized slot with recorded transactions, the fraction of transactions
+    /// that paid zero prioritization fee. A low ratio indicates a congested block.
+    pub fn zero_fee_ratio_by_slot(&self) -> BTreeMap<Slot, f64> {
+        read_lock(&self.cache)
+            .iter()
+            .filter_map(|(slot, slot_prioritization_fee)| {
+                slot_prioritization_fee
+                    .get_zero_fee_transaction_ratio()
+                    .map(|ratio| (*slot, ratio))
+            })
+            .collect()
+    }
+
+    /// Recommends a compute unit price for a transaction write-locking `writable_accounts`. Each
+    /// account's fee at `RECOMMENDED_FEE_PERCENTILE` of available blocks is computed, and the
+    /// highest is returned since the most contended account is the binding constraint. Returns
+    /// None if no block is available.
+    pub fn recommend_fee_for_transaction(&self, writable_accounts: &[Pubkey]) -> Option<u64> {
+        let block_fee = self.get_prioritization_fee_percentile(RECOMMENDED_FEE_PERCENTILE)?;
+        Some(
+            writable_accounts
+                .iter()
+                .filter_map(|account| {
+                    Self::percentile_of_sorted(
+                        &self.sorted_fees(std::slice::from_ref(account)),
+                        RECOMMENDED_FEE_PERCENTILE,
+                    )
+                })
+                .fold(block_fee, u64::max),
+        )
+    }
+
+    /// Returns the block minimum compute unit price at `percentile` of available blocks, or None
+    /// if no block is available or `percentile` is greater than 100.
+    pub fn get_prioritization_fee_percentile(&self, percentile: u8) -> Option<u64> {
+        Self::percentile_of_sorted(&self.sorted_fees(&[]), percentile)
+    }
+
+    /// Same as `get_prioritization_fee_percentile` for each of `percentiles`, sorting block
+    /// minimum fees only once.
+    pub fn get_prioritization_fee_percentiles(&self, percentiles: &[u8]) -> Vec<Option<u64>> {
+        let fees = self.sorted_fees(&[]);
+        percentiles
+            .iter()
+            .map(|percentile| Self::percentile_of_sorted(&fees, *percentile))
+            .collect()
+    }
+
+    /// Returns `get_prioritization_fees(account_keys)` fees of available blocks, sorted.
+    fn sorted_fees(&self, account_keys: &[Pubkey]) -> Vec<u64> {
+        let mut fees: Vec<_> = self
+            .get_prioritization_fees(account_keys)
+            .into_iter()
+            .map(|(_slot, fee)| fee)
+            .collect();
+        fees.sort_unstable();
+        fees
+    }
+
+    fn percentile_of_sorted(sorted_fees: &[u64], percentile: u8) -> Option<u64> {
+        if percentile > 100 || sorted_fees.is_empty() {
+            return None;
+        }
+        let index = (sorted_fees.len() - 1) * usize::from(percentile) / 100;
+        Some(sorted_fees[index])
+    }
+
+    /// Estimates the effective price, in micro-lamports per compute unit, a transaction with
+    /// `assumed_cu_limit` pays if it offers the block minimum compute unit price at `percentile`
+    /// of available blocks. The prioritization fee is charged in whole lamports, so for small
+    /// limits the effective price can exceed the offered one. Returns None if no block is
+    /// available, `percentile` is greater than 100, or `assumed_cu_limit` is zero.
+    pub fn estimate_micro_lamports_per_cu(
+        &self,
+        percentile: u8,
+        assumed_cu_limit: u32,
+    ) -> Option<u64> {
+        if assumed_cu_limit == 0 {
+            return None;
+        }
+        let compute_unit_price = self.get_prioritization_fee_percentile(percentile)?;
+
+        let fee_budget_limits = FeeBudgetLimits::from(ComputeBudgetLimits {
+            compute_unit_limit: assumed_cu_limit,
+            compute_unit_price,
+            ..ComputeBudgetLimits::default()
+        });
+        Some(break_even_cu_price(
+            fee_budget_limits.prioritization_fee,
+            assumed_cu_limit,
+        ))
+    }
+
+    /// Returns the smallest compute unit price that meets or exceeds the block minimum fee of at
+    /// least `target` fraction of available blocks, that is, the price whose landing probability
+    /// estimated from recent blocks is no less than `target`. Returns None if no block is
+    /// available or `target` is not within `[0.0, 1.0]`.
+    pub fn fee_for_target_landing_probability(&self, target: f64) -> Option<u64> {
+        if !(0.0..=1.0).contains(&target) {
+            return None;
+        }
+
+        let fees = self.sorted_fees(&[]);
+        if fees.is_empty() {
+            return None;
+        }
+        if target == 0.0 {
+            return Some(0);
+        }
+        let num_blocks = fees.len() as f64;
+        fees.into_iter()
+            .enumerate()
+            .find(|(index, _fee)| (index + 1) as f64 / num_blocks >= target)
+            .map(|(_index, fee)| fee)
+    }
+}
+
+/// Acquires read lock, recovering the guard if the lock is poisoned, so a thread panicking while
+/// holding the lock doesn't take down the whole fee subsystem.
+fn read_lock<T>(lock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+    lock.read().unwrap_or_else(|err| {
+        warn!("prioritization fee cache lock is poisoned, recovering");
+        err.into_inner()
+    })
+}
+
+/// Acquires write lock, recovering the guard if the lock is poisoned.
+fn write_lock<T>(lock: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+    lock.write().unwrap_or_else(|err| {
+        warn!("prioritization fee cache lock is poisoned, recovering");
+        err.into_inner()
+    })
+}
+
+/// Acquires mutex lock, recovering the guard if the lock is poisoned.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|err| {
+        warn!("prioritization fee cache lock is poisoned, recovering");
+        err.into_inner()
+    })
 }
 
 #[cfg(test)]
@@ -502,7 +1083,7 @@ mod tests {
 
         // wait till finalization is done
         loop {
-            let cache = prioritization_fee_cache.cache.read().unwrap();
+            let cache = read_lock(&prioritization_fee_cache.cache);
             if let Some(slot_cache) = cache.get(&slot) {
                 if slot_cache.is_finalized() {
                     return;
@@ -560,6 +1141,70 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_synchronous_cache() {
+        solana_logger::setup();
+        let write_account_a = Pubkey::new_unique();
+        let write_account_b = Pubkey::new_unique();
+
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(10_000);
+        let bank0 = Bank::new_for_benches(&genesis_config);
+        let bank_forks = BankForks::new_rw_arc(bank0);
+        let bank = bank_forks.read().unwrap().working_bank();
+        let collector = solana_pubkey::new_rand();
+        let bank1 = Arc::new(Bank::new_from_parent(bank.clone(), &collector, 1));
+        let bank2 = Arc::new(Bank::new_from_parent(bank, &collector, 2));
+
+        let prioritization_fee_cache =
+            PrioritizationFeeCache::new_with_config(PrioritizationFeeCacheConfig {
+                capacity: 2,
+                synchronous: true,
+                ..PrioritizationFeeCacheConfig::default()
+            });
+        let txs = vec![
+            build_sanitized_transaction_for_test(5, &Pubkey::new_unique(), &write_account_a),
+            build_sanitized_transaction_for_test(9, &Pubkey::new_unique(), &write_account_b),
+        ];
+        prioritization_fee_cache.update(&bank1, txs.iter());
+        assert_eq!(
+            2,
+            prioritization_fee_cache
+                .metrics
+                .successful_transaction_update_count
+                .load(Ordering::Relaxed)
+        );
+        // updates are not visible to queries until the slot is finalized
+        assert_eq!(0, prioritization_fee_cache.available_block_count());
+
+        prioritization_fee_cache.finalize_priority_fee(1, bank1.bank_id());
+        assert_eq!(
+            vec![(1, 5)],
+            prioritization_fee_cache.get_prioritization_fees(&[])
+        );
+        assert_eq!(
+            vec![(1, 9)],
+            prioritization_fee_cache.get_prioritization_fees(&[write_account_b])
+        );
+
+        let txs = vec![build_sanitized_transaction_for_test(
+            3,
+            &Pubkey::new_unique(),
+            &write_account_a,
+        )];
+        prioritization_fee_cache.update(&bank2, txs.iter());
+        prioritization_fee_cache.finalize_priority_fee(2, bank2.bank_id());
+        assert_eq!(
+            vec![(1, 5), (2, 3)],
+            prioritization_fee_cache.get_prioritization_fees(&[])
+        );
+
+        prioritization_fee_cache.abandon_slot(1);
+        assert_eq!(
+            vec![(2, 3)],
+            prioritization_fee_cache.get_prioritization_fees(&[])
+        );
+    }
+
     #[test]
     fn test_available_block_count() {
         let prioritization_fee_cache = PrioritizationFeeCache::default();
@@ -609,6 +1254,102 @@ mod tests {
         assert_eq!(2, prioritization_fee_cache.available_block_count());
     }
 
+    #[test]
+    fn test_account_fee_sample_count() {
+        let account = Pubkey::new_unique();
+        let prioritization_fee_cache = PrioritizationFeeCache::default();
+        assert_eq!(
+            0,
+            prioritization_fee_cache.account_fee_sample_count(&account)
+        );
+
+        prioritization_fee_cache.import_snapshot(PrioritizationFeeSnapshot {
+            slots: (1..=3)
+                .map(|slot| {
+                    let min_writable_account_fees = if slot == 2 {
+                        HashMap::new()
+                    } else {
+                        HashMap::from([(account, 100)])
+                    };
+                    (
+                        slot,
+                        SlotPrioritizationFeeSnapshot {
+                            min_transaction_fee: Some(10),
+                            min_writable_account_fees,
+                        },
+                    )
+                })
+                .collect(),
+        });
+
+        assert_eq!(
+            2,
+            prioritization_fee_cache.account_fee_sample_count(&account)
+        );
+        assert_eq!(
+            0,
+            prioritization_fee_cache.account_fee_sample_count(&Pubkey::new_unique())
+        );
+    }
+
+    #[test]
+    fn test_covered_slot_range() {
+        let prioritization_fee_cache = PrioritizationFeeCache::default();
+        assert_eq!(None, prioritization_fee_cache.covered_slot_range());
+
+        for slot in [5, 10, 3] {
+            prioritization_fee_cache.import_snapshot(PrioritizationFeeSnapshot {
+                slots: BTreeMap::from([(
+                    slot,
+                    SlotPrioritizationFeeSnapshot {
+                        min_transaction_fee: Some(slot * 100),
+                        min_writable_account_fees: HashMap::new(),
+                    },
+                )]),
+            });
+        }
+        assert_eq!(3, prioritization_fee_cache.available_block_count());
+        assert_eq!(Some((3, 10)), prioritization_fee_cache.covered_slot_range());
+        assert_eq!(
+            Some((3, 10)),
+            prioritization_fee_cache.finalized_slot_range()
+        );
+
+        // a slot updated through `update()` is covered right away, and counts as finalized once
+        // finalized
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(10_000);
+        let bank0 = Bank::new_for_benches(&genesis_config);
+        let bank_forks = BankForks::new_rw_arc(bank0);
+        let bank = bank_forks.read().unwrap().working_bank();
+        let bank11 = Arc::new(Bank::new_from_parent(bank, &solana_pubkey::new_rand(), 11));
+        let txs = vec![build_sanitized_transaction_for_test(
+            5,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+        )];
+        sync_update(&prioritization_fee_cache, bank11.clone(), txs.iter());
+        assert_eq!(Some((3, 11)), prioritization_fee_cache.covered_slot_range());
+        assert_eq!(
+            Some((3, 10)),
+            prioritization_fee_cache.finalized_slot_range()
+        );
+        sync_finalize_priority_fee_for_test(&prioritization_fee_cache, 11, bank11.bank_id());
+        assert_eq!(Some((3, 11)), prioritization_fee_cache.covered_slot_range());
+        assert_eq!(
+            Some((3, 11)),
+            prioritization_fee_cache.finalized_slot_range()
+        );
+
+        // an unfinalized slot alone is covered, but not finalized
+        let prioritization_fee_cache = PrioritizationFeeCache::default();
+        sync_update(&prioritization_fee_cache, bank11, txs.iter());
+        assert_eq!(
+            Some((11, 11)),
+            prioritization_fee_cache.covered_slot_range()
+        );
+        assert_eq!(None, prioritization_fee_cache.finalized_slot_range());
+    }
+
     #[test]
     fn test_get_prioritization_fees() {
         solana_logger::setup();
@@ -941,4 +1682,825 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_abandon_slot() {
+        solana_logger::setup();
+        let write_account_a = Pubkey::new_unique();
+        let write_account_b = Pubkey::new_unique();
+
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(10_000);
+        let bank0 = Bank::new_for_benches(&genesis_config);
+        let bank_forks = BankForks::new_rw_arc(bank0);
+        let bank = bank_forks.read().unwrap().working_bank();
+        let collector = solana_pubkey::new_rand();
+        let bank1 = Arc::new(Bank::new_from_parent(bank.clone(), &collector, 1));
+        let bank2 = Arc::new(Bank::new_from_parent(bank.clone(), &collector, 2));
+        let bank3 = Arc::new(Bank::new_from_parent(bank, &collector, 3));
+
+        let prioritization_fee_cache = PrioritizationFeeCache::default();
+        let txs = vec![build_sanitized_transaction_for_test(
+            2,
+            &write_account_a,
+            &write_account_b,
+        )];
+
+        // abandon a finalized slot, it is no longer available for queries
+        {
+            sync_update(&prioritization_fee_cache, bank1.clone(), txs.iter());
+            sync_finalize_priority_fee_for_test(&prioritization_fee_cache, 1, bank1.bank_id());
+            assert_eq!(1, prioritization_fee_cache.available_block_count());
+            assert_eq!(
+                vec![(1, 2)],
+                prioritization_fee_cache.get_prioritization_fees(&[write_account_a])
+            );
+
+            prioritization_fee_cache.abandon_slot(1);
+            assert_eq!(0, prioritization_fee_cache.available_block_count());
+            assert!(prioritization_fee_cache
+                .get_prioritization_fees(&[write_account_a])
+                .is_empty());
+        }
+
+        // abandon an unfinalized slot, its later finalization has nothing to finalize
+        {
+            sync_update(&prioritization_fee_cache, bank2.clone(), txs.iter());
+            prioritization_fee_cache.abandon_slot(2);
+            prioritization_fee_cache.finalize_priority_fee(2, bank2.bank_id());
+
+            // service thread handles updates in order, once slot 3 is finalized, slot 2 has
+            // been processed too.
+            sync_update(&prioritization_fee_cache, bank3.clone(), txs.iter());
+            sync_finalize_priority_fee_for_test(&prioritization_fee_cache, 3, bank3.bank_id());
+            assert_eq!(1, prioritization_fee_cache.available_block_count());
+            assert_eq!(
+                vec![(3, 2)],
+                prioritization_fee_cache.get_prioritization_fees(&[write_account_a])
+            );
+        }
+    }
+
+    #[test]
+    fn test_poisoned_cache_lock() {
+        solana_logger::setup();
+        let write_account_a = Pubkey::new_unique();
+        let write_account_b = Pubkey::new_unique();
+
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(10_000);
+        let bank0 = Bank::new_for_benches(&genesis_config);
+        let bank_forks = BankForks::new_rw_arc(bank0);
+        let bank = bank_forks.read().unwrap().working_bank();
+        let collector = solana_pubkey::new_rand();
+        let bank1 = Arc::new(Bank::new_from_parent(bank, &collector, 1));
+
+        let prioritization_fee_cache = PrioritizationFeeCache::default();
+
+        // poison the cache lock by panicking while holding it
+        let cache = prioritization_fee_cache.cache.clone();
+        assert!(std::thread::spawn(move || {
+            let _cache = cache.write().unwrap();
+            panic!("poison prioritization fee cache lock");
+        })
+        .join()
+        .is_err());
+        assert!(prioritization_fee_cache.cache.is_poisoned());
+
+        // cache continues to function
+        assert_eq!(0, prioritization_fee_cache.available_block_count());
+        let txs = vec![build_sanitized_transaction_for_test(
+            2,
+            &write_account_a,
+            &write_account_b,
+        )];
+        sync_update(&prioritization_fee_cache, bank1.clone(), txs.iter());
+        sync_finalize_priority_fee_for_test(&prioritization_fee_cache, 1, bank1.bank_id());
+        assert_eq!(1, prioritization_fee_cache.available_block_count());
+        assert_eq!(
+            vec![(1, 2)],
+            prioritization_fee_cache.get_prioritization_fees(&[write_account_a])
+        );
+
+        prioritization_fee_cache.abandon_slot(1);
+        assert_eq!(0, prioritization_fee_cache.available_block_count());
+    }
+
+    #[test]
+    fn test_estimate_micro_lamports_per_cu() {
+        solana_logger::setup();
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(10_000);
+        let bank0 = Bank::new_for_benches(&genesis_config);
+        let bank_forks = BankForks::new_rw_arc(bank0);
+        let bank = bank_forks.read().unwrap().working_bank();
+        let collector = solana_pubkey::new_rand();
+
+        let prioritization_fee_cache = PrioritizationFeeCache::default();
+        assert_eq!(
+            None,
+            prioritization_fee_cache.estimate_micro_lamports_per_cu(50, 200_000)
+        );
+
+        // compute unit prices, in micro-lamports, of 5 blocks
+        for (slot, compute_unit_price) in [
+            (1, 3_000_000),
+            (2, 1_000_000),
+            (3, 5_000_000),
+            (4, 2_000_000),
+            (5, 1_500_000),
+        ] {
+            let bank = Arc::new(Bank::new_from_parent(bank.clone(), &collector, slot));
+            let txs = vec![build_sanitized_transaction_for_test(
+                compute_unit_price,
+                &Pubkey::new_unique(),
+                &Pubkey::new_unique(),
+            )];
+            sync_update(&prioritization_fee_cache, bank.clone(), txs.iter());
+            sync_finalize_priority_fee_for_test(&prioritization_fee_cache, slot, bank.bank_id());
+        }
+
+        assert_eq!(
+            Some(1_000_000),
+            prioritization_fee_cache.estimate_micro_lamports_per_cu(0, 200_000)
+        );
+        assert_eq!(
+            Some(2_000_000),
+            prioritization_fee_cache.estimate_micro_lamports_per_cu(50, 200_000)
+        );
+        assert_eq!(
+            Some(5_000_000),
+            prioritization_fee_cache.estimate_micro_lamports_per_cu(100, 200_000)
+        );
+        // fractional lamports are rounded up: a 4_500_000 micro-lamports fee is charged as 5
+        // lamports, so 3 compute units effectively pay 1_666_666 micro-lamports each
+        assert_eq!(
+            Some(1_666_666),
+            prioritization_fee_cache.estimate_micro_lamports_per_cu(25, 3)
+        );
+
+        assert_eq!(
+            None,
+            prioritization_fee_cache.estimate_micro_lamports_per_cu(101, 200_000)
+        );
+        assert_eq!(
+            None,
+            prioritization_fee_cache.estimate_micro_lamports_per_cu(50, 0)
+        );
+    }
+
+    #[test]
+    fn test_get_prioritization_fee_percentiles() {
+        let prioritization_fee_cache = PrioritizationFeeCache::default();
+        let percentiles = [25, 50, 75, 95, 100, 101];
+        assert_eq!(
+            vec![None; percentiles.len()],
+            prioritization_fee_cache.get_prioritization_fee_percentiles(&percentiles)
+        );
+
+        prioritization_fee_cache.import_snapshot(PrioritizationFeeSnapshot {
+            slots: (1..=10)
+                .map(|slot| {
+                    (
+                        slot,
+                        SlotPrioritizationFeeSnapshot {
+                            min_transaction_fee: Some((11 - slot) * 100),
+                            min_writable_account_fees: HashMap::new(),
+                        },
+                    )
+                })
+                .collect(),
+        });
+
+        let expected_fees: Vec<_> = percentiles
+            .iter()
+            .map(|percentile| {
+                prioritization_fee_cache.get_prioritization_fee_percentile(*percentile)
+            })
+            .collect();
+        assert_eq!(
+            vec![Some(300), Some(500), Some(700), Some(900), Some(1000), None],
+            expected_fees
+        );
+        assert_eq!(
+            expected_fees,
+            prioritization_fee_cache.get_prioritization_fee_percentiles(&percentiles)
+        );
+        assert!(prioritization_fee_cache
+            .get_prioritization_fee_percentiles(&[])
+            .is_empty());
+    }
+
+    #[test]
+    fn test_recommend_fee_for_transaction() {
+        let hot_account = Pubkey::new_unique();
+        let warm_account = Pubkey::new_unique();
+        let prioritization_fee_cache = PrioritizationFeeCache::default();
+        assert_eq!(
+            None,
+            prioritization_fee_cache.recommend_fee_for_transaction(&[hot_account])
+        );
+
+        // hot account is written at increasing fees in every block, warm account only in the
+        // first two blocks at a modest fee.
+        prioritization_fee_cache.import_snapshot(PrioritizationFeeSnapshot {
+            slots: (1..=4)
+                .map(|slot| {
+                    let mut min_writable_account_fees = HashMap::from([(hot_account, slot * 1000)]);
+                    if slot <= 2 {
+                        min_writable_account_fees.insert(warm_account, 50);
+                    }
+                    (
+                        slot,
+                        SlotPrioritizationFeeSnapshot {
+                            min_transaction_fee: Some(10),
+                            min_writable_account_fees,
+                        },
+                    )
+                })
+                .collect(),
+        });
+
+        // without account history, fall back to block minimum fees
+        assert_eq!(
+            Some(10),
+            prioritization_fee_cache.recommend_fee_for_transaction(&[])
+        );
+        assert_eq!(
+            Some(10),
+            prioritization_fee_cache.recommend_fee_for_transaction(&[Pubkey::new_unique()])
+        );
+        // 75th percentile of [10, 10, 50, 50]
+        assert_eq!(
+            Some(50),
+            prioritization_fee_cache.recommend_fee_for_transaction(&[warm_account])
+        );
+        // 75th percentile of [1000, 2000, 3000, 4000] is the binding constraint
+        assert_eq!(
+            Some(3000),
+            prioritization_fee_cache.recommend_fee_for_transaction(&[warm_account, hot_account])
+        );
+    }
+
+    #[test]
+    fn test_fee_for_target_landing_probability() {
+        let prioritization_fee_cache = PrioritizationFeeCache::default();
+        assert_eq!(
+            None,
+            prioritization_fee_cache.fee_for_target_landing_probability(0.5)
+        );
+
+        prioritization_fee_cache.import_snapshot(PrioritizationFeeSnapshot {
+            slots: [(1, 9), (2, 2), (3, 5)]
+                .into_iter()
+                .map(|(slot, min_transaction_fee)| {
+                    (
+                        slot,
+                        SlotPrioritizationFeeSnapshot {
+                            min_transaction_fee: Some(min_transaction_fee),
+                            min_writable_account_fees: HashMap::new(),
+                        },
+                    )
+                })
+                .collect(),
+        });
+
+        for (target, expected_fee) in [
+            (0.0, Some(0)),
+            (0.3, Some(2)),
+            (0.34, Some(5)),
+            (0.66, Some(5)),
+            (0.67, Some(9)),
+            (1.0, Some(9)),
+            (-0.1, None),
+            (1.1, None),
+        ] {
+            assert_eq!(
+                expected_fee,
+                prioritization_fee_cache.fee_for_target_landing_probability(target),
+                "target {target}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_account_fee_stats() {
+        let prioritization_fee_cache = PrioritizationFeeCache::default();
+        assert_eq!(
+            AccountFeeStats::default(),
+            prioritization_fee_cache.account_fee_stats()
+        );
+
+        let (write_account_a, write_account_b, write_account_c) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        prioritization_fee_cache.import_snapshot(PrioritizationFeeSnapshot {
+            slots: BTreeMap::from([
+                (
+                    1,
+                    SlotPrioritizationFeeSnapshot {
+                        min_transaction_fee: Some(1),
+                        min_writable_account_fees: HashMap::from([
+                            (write_account_a, 8),
+                            (write_account_b, 3),
+                        ]),
+                    },
+                ),
+                (
+                    2,
+                    SlotPrioritizationFeeSnapshot {
+                        min_transaction_fee: Some(1),
+                        min_writable_account_fees: HashMap::from([
+                            (write_account_a, 6),
+                            (write_account_c, 20),
+                        ]),
+                    },
+                ),
+            ]),
+        });
+
+        // account a contributes its lowest fee 6
+        assert_eq!(
+            AccountFeeStats {
+                distinct_accounts: 3,
+                min: 3,
+                max: 20,
+                median: 6,
+            },
+            prioritization_fee_cache.account_fee_stats()
+        );
+    }
+
+    #[test]
+    fn test_update_cache_writable_accounts() {
+        let slot = 1;
+        let bank_id = 0;
+        let metrics = PrioritizationFeeCacheMetrics::default();
+        let mut unfinalized = UnfinalizedPrioritizationFees::new();
+
+        // transaction without writable account only updates block minimum fee
+        PrioritizationFeeCache::update_cache(
+            &mut unfinalized,
+            slot,
+            bank_id,
+            5,
+            0,
+            true,
+            WritableAccounts::new(),
+            &metrics,
+        );
+        let prioritization_fee = &unfinalized[&slot][&bank_id];
+        assert_eq!(Some(5), prioritization_fee.get_min_transaction_fee());
+        assert_eq!(0, prioritization_fee.get_writable_accounts_count());
+
+        // writable accounts beyond inline capacity are all tracked
+        let write_accounts: Vec<_> = (0..INLINE_WRITABLE_ACCOUNTS * 8)
+            .map(|_| Pubkey::new_unique())
+            .collect();
+        let writable_accounts: WritableAccounts = write_accounts.iter().copied().collect();
+        assert!(writable_accounts.spilled());
+        PrioritizationFeeCache::update_cache(
+            &mut unfinalized,
+            slot,
+            bank_id,
+            7,
+            0,
+            true,
+            writable_accounts,
+            &metrics,
+        );
+        let prioritization_fee = &unfinalized[&slot][&bank_id];
+        assert_eq!(Some(5), prioritization_fee.get_min_transaction_fee());
+        assert_eq!(
+            write_accounts.len(),
+            prioritization_fee.get_writable_accounts_count()
+        );
+        for write_account in &write_accounts {
+            assert_eq!(
+                Some(7),
+                prioritization_fee.get_writable_account_fee(write_account)
+            );
+        }
+        assert_eq!(
+            2,
+            metrics
+                .successful_transaction_update_count
+                .load(Ordering::Relaxed)
+        );
+    }
+
+    #[test]
+    fn test_finalize_slot_evicts_expired_slots() {
+        let bank_id = 0;
+        let max_slot_age = Duration::from_secs(10);
+        let metrics = PrioritizationFeeCacheMetrics::default();
+        let cache = RwLock::new(BTreeMap::new());
+        let mut unfinalized = UnfinalizedPrioritizationFees::new();
+
+        let finalize = |unfinalized: &mut UnfinalizedPrioritizationFees, slot, now| {
+            PrioritizationFeeCache::update_cache(
+                unfinalized,
+                slot,
+                bank_id,
+                1,
+                0,
+                true,
+                WritableAccounts::new(),
+                &metrics,
+            );
+            PrioritizationFeeCache::finalize_slot(
+                unfinalized,
+                &cache,
+                MAX_NUM_RECENT_BLOCKS as usize,
+                Some(max_slot_age),
+                now,
+                slot,
+                bank_id,
+                &metrics,
+            );
+        };
+
+        finalize(&mut unfinalized, 1, Instant::now());
+        let first_update_time = read_lock(&cache)[&1].first_update_time();
+
+        // slot 1 is still within the age limit
+        finalize(&mut unfinalized, 2, first_update_time + max_slot_age);
+        assert_eq!(
+            vec![1, 2],
+            read_lock(&cache).keys().copied().collect::<Vec<_>>()
+        );
+
+        // advancing the clock past the age limit evicts the stale slots on next finalization,
+        // while the slot being finalized is always kept
+        finalize(
+            &mut unfinalized,
+            3,
+            first_update_time + max_slot_age + Duration::from_secs(1),
+        );
+        assert_eq!(
+            vec![3],
+            read_lock(&cache).keys().copied().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_finalize_grace_period() {
+        solana_logger::setup();
+        let write_account = Pubkey::new_unique();
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(10_000);
+        let bank0 = Bank::new_for_benches(&genesis_config);
+        let bank_forks = BankForks::new_rw_arc(bank0);
+        let bank = bank_forks.read().unwrap().working_bank();
+        let collector = solana_pubkey::new_rand();
+        let bank1 = Arc::new(Bank::new_from_parent(bank, &collector, 1));
+
+        let prioritization_fee_cache =
+            PrioritizationFeeCache::new_with_config(PrioritizationFeeCacheConfig {
+                finalize_grace_period: Some(Duration::from_secs(1)),
+                ..PrioritizationFeeCacheConfig::default()
+            });
+
+        let txs = vec![build_sanitized_transaction_for_test(
+            5,
+            &Pubkey::new_unique(),
+            &write_account,
+        )];
+        sync_update(&prioritization_fee_cache, bank1.clone(), txs.iter());
+        prioritization_fee_cache.finalize_priority_fee(1, bank1.bank_id());
+
+        // an update arriving within grace period is still accepted
+        let txs = vec![build_sanitized_transaction_for_test(
+            2,
+            &Pubkey::new_unique(),
+            &write_account,
+        )];
+        sync_update(&prioritization_fee_cache, bank1.clone(), txs.iter());
+        assert_eq!(0, prioritization_fee_cache.available_block_count());
+
+        sync_finalize_priority_fee_for_test(&prioritization_fee_cache, 1, bank1.bank_id());
+        assert_eq!(
+            vec![(1, 2)],
+            prioritization_fee_cache.get_prioritization_fees(&[write_account])
+        );
+    }
+
+    #[test]
+    fn test_min_countable_fee() {
+        solana_logger::setup();
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(10_000);
+        let bank0 = Bank::new_for_benches(&genesis_config);
+        let bank_forks = BankForks::new_rw_arc(bank0);
+        let bank = bank_forks.read().unwrap().working_bank();
+        let collector = solana_pubkey::new_rand();
+        let bank1 = Arc::new(Bank::new_from_parent(bank, &collector, 1));
+
+        let prioritization_fee_cache =
+            PrioritizationFeeCache::new_with_config(PrioritizationFeeCacheConfig {
+                min_countable_fee: 1,
+                ..PrioritizationFeeCacheConfig::default()
+            });
+
+        // zero fee transactions are below the threshold, they are excluded from minimum fees only
+        let zero_fee_txs: Vec<_> = (0..10)
+            .map(|_| {
+                build_sanitized_transaction_for_test(
+                    0,
+                    &Pubkey::new_unique(),
+                    &Pubkey::new_unique(),
+                )
+            })
+            .collect();
+        sync_update(
+            &prioritization_fee_cache,
+            bank1.clone(),
+            zero_fee_txs.iter(),
+        );
+        let txs = vec![build_sanitized_transaction_for_test(
+            10,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+        )];
+        sync_update(&prioritization_fee_cache, bank1.clone(), txs.iter());
+        sync_finalize_priority_fee_for_test(&prioritization_fee_cache, 1, bank1.bank_id());
+
+        assert_eq!(
+            vec![(1, 10)],
+            prioritization_fee_cache.get_prioritization_fees(&[])
+        );
+        assert_eq!(
+            Some(10),
+            prioritization_fee_cache.fee_for_target_landing_probability(1.0)
+        );
+        // still counted in the slot's transaction counts
+        assert_eq!(
+            BTreeMap::from([(1, 10.0 / 11.0)]),
+            prioritization_fee_cache.zero_fee_ratio_by_slot()
+        );
+    }
+
+    #[test]
+    fn test_zero_fee_ratio_by_slot() {
+        solana_logger::setup();
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(10_000);
+        let bank0 = Bank::new_for_benches(&genesis_config);
+        let bank_forks = BankForks::new_rw_arc(bank0);
+        let bank = bank_forks.read().unwrap().working_bank();
+        let collector = solana_pubkey::new_rand();
+        let prioritization_fee_cache = PrioritizationFeeCache::default();
+        assert!(prioritization_fee_cache.zero_fee_ratio_by_slot().is_empty());
+
+        // slot 1: one of four transactions paid zero fee
+        let bank1 = Arc::new(Bank::new_from_parent(bank.clone(), &collector, 1));
+        let txs: Vec<_> = [0, 10, 20, 30]
+            .into_iter()
+            .map(|fee| {
+                build_sanitized_transaction_for_test(
+                    fee,
+                    &Pubkey::new_unique(),
+                    &Pubkey::new_unique(),
+                )
+            })
+            .collect();
+        sync_update(&prioritization_fee_cache, bank1.clone(), txs.iter());
+        sync_finalize_priority_fee_for_test(&prioritization_fee_cache, 1, bank1.bank_id());
+
+        // slot 2: all transactions paid zero fee
+        let bank2 = Arc::new(Bank::new_from_parent(bank, &collector, 2));
+        let txs: Vec<_> = (0..2)
+            .map(|_| {
+                build_sanitized_transaction_for_test(
+                    0,
+                    &Pubkey::new_unique(),
+                    &Pubkey::new_unique(),
+                )
+            })
+            .collect();
+        sync_update(&prioritization_fee_cache, bank2.clone(), txs.iter());
+        sync_finalize_priority_fee_for_test(&prioritization_fee_cache, 2, bank2.bank_id());
+
+        // slot 3: imported without transaction counts, so it has no ratio
+        prioritization_fee_cache.import_snapshot(PrioritizationFeeSnapshot {
+            slots: BTreeMap::from([(
+                3,
+                SlotPrioritizationFeeSnapshot {
+                    min_transaction_fee: Some(0),
+                    min_writable_account_fees: HashMap::new(),
+                },
+            )]),
+        });
+
+        assert_eq!(
+            BTreeMap::from([(1, 0.25), (2, 1.0)]),
+            prioritization_fee_cache.zero_fee_ratio_by_slot()
+        );
+    }
+
+    #[test]
+    fn test_slot_total_priority_fee() {
+        solana_logger::setup();
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(10_000);
+        let bank0 = Bank::new_for_benches(&genesis_config);
+        let bank_forks = BankForks::new_rw_arc(bank0);
+        let bank = bank_forks.read().unwrap().working_bank();
+        let collector = solana_pubkey::new_rand();
+        let bank1 = Arc::new(Bank::new_from_parent(bank, &collector, 1));
+
+        let prioritization_fee_cache = PrioritizationFeeCache::default();
+        // with a limit of one million compute units, a compute unit price of N micro-lamports
+        // costs N lamports of prioritization fee.
+        let txs: Vec<_> = [5, 9, 2]
+            .into_iter()
+            .map(|compute_unit_price| {
+                let signer_account = Pubkey::new_unique();
+                RuntimeTransaction::from_transaction_for_tests(Transaction::new_unsigned(
+                    Message::new(
+                        &[
+                            system_instruction::transfer(&signer_account, &Pubkey::new_unique(), 1),
+                            ComputeBudgetInstruction::set_compute_unit_limit(1_000_000),
+                            ComputeBudgetInstruction::set_compute_unit_price(compute_unit_price),
+                        ],
+                        Some(&signer_account),
+                    ),
+                ))
+            })
+            .collect();
+        sync_update(&prioritization_fee_cache, bank1.clone(), txs.iter());
+
+        // not available until slot is finalized
+        assert_eq!(None, prioritization_fee_cache.slot_total_priority_fee(1));
+
+        sync_finalize_priority_fee_for_test(&prioritization_fee_cache, 1, bank1.bank_id());
+        assert_eq!(
+            Some(16),
+            prioritization_fee_cache.slot_total_priority_fee(1)
+        );
+        assert_eq!(None, prioritization_fee_cache.slot_total_priority_fee(2));
+    }
+
+    #[test]
+    fn test_import_snapshot() {
+        solana_logger::setup();
+        let write_account_a = Pubkey::new_unique();
+        let write_account_b = Pubkey::new_unique();
+        let write_account_c = Pubkey::new_unique();
+
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(10_000);
+        let bank0 = Bank::new_for_benches(&genesis_config);
+        let bank_forks = BankForks::new_rw_arc(bank0);
+        let bank = bank_forks.read().unwrap().working_bank();
+        let collector = solana_pubkey::new_rand();
+
+        let source_cache = PrioritizationFeeCache::default();
+        for (slot, txs) in [
+            (
+                1,
+                vec![
+                    build_sanitized_transaction_for_test(2, &write_account_a, &write_account_b),
+                    build_sanitized_transaction_for_test(5, &write_account_c, &write_account_b),
+                ],
+            ),
+            (
+                2,
+                vec![
+                    build_sanitized_transaction_for_test(3, &write_account_a, &write_account_c),
+                    build_sanitized_transaction_for_test(1, &write_account_b, &write_account_b),
+                ],
+            ),
+        ] {
+            let bank = Arc::new(Bank::new_from_parent(bank.clone(), &collector, slot));
+            sync_update(&source_cache, bank.clone(), txs.iter());
+            sync_finalize_priority_fee_for_test(&source_cache, slot, bank.bank_id());
+        }
+
+        let snapshot = source_cache.snapshot();
+        assert_eq!(2, snapshot.slots.len());
+
+        // import into an empty cache, queries match
+        let target_cache = PrioritizationFeeCache::default();
+        target_cache.import_snapshot(snapshot.clone());
+        assert_eq!(2, target_cache.available_block_count());
+        for account_keys in [
+            vec![],
+            vec![write_account_a],
+            vec![write_account_b],
+            vec![write_account_c],
+            vec![write_account_a, write_account_b, write_account_c],
+        ] {
+            assert_eq!(
+                source_cache.get_prioritization_fees(&account_keys),
+                target_cache.get_prioritization_fees(&account_keys)
+            );
+        }
+        assert_eq!(snapshot, target_cache.snapshot());
+
+        // slots already present are not overwritten
+        let mut conflicting_snapshot = PrioritizationFeeSnapshot::default();
+        conflicting_snapshot.slots.insert(
+            1,
+            SlotPrioritizationFeeSnapshot {
+                min_transaction_fee: Some(100),
+                min_writable_account_fees: HashMap::new(),
+            },
+        );
+        conflicting_snapshot.slots.insert(
+            3,
+            SlotPrioritizationFeeSnapshot {
+                min_transaction_fee: Some(7),
+                min_writable_account_fees: HashMap::new(),
+            },
+        );
+        target_cache.import_snapshot(conflicting_snapshot);
+        assert_eq!(
+            vec![(1, 2), (2, 1), (3, 7)],
+            target_cache.get_prioritization_fees(&[])
+        );
+
+        // import evicts oldest slots beyond capacity
+        let small_cache = PrioritizationFeeCache::new(1);
+        small_cache.import_snapshot(snapshot);
+        assert_eq!(vec![(2, 1)], small_cache.get_prioritization_fees(&[]));
+    }
+
+    #[test]
+    fn test_get_prioritization_fees_owned() {
+        solana_logger::setup();
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(10_000);
+        let bank0 = Bank::new_for_benches(&genesis_config);
+        let bank_forks = BankForks::new_rw_arc(bank0);
+        let bank = bank_forks.read().unwrap().working_bank();
+        let collector = solana_pubkey::new_rand();
+
+        let prioritization_fee_cache = PrioritizationFeeCache::default();
+        assert!(prioritization_fee_cache
+            .get_prioritization_fees_owned()
+            .is_empty());
+
+        let add_block = |slot: Slot, fee: u64| {
+            let bank = Arc::new(Bank::new_from_parent(bank.clone(), &collector, slot));
+            let txs = [build_sanitized_transaction_for_test(
+                fee,
+                &Pubkey::new_unique(),
+                &Pubkey::new_unique(),
+            )];
+            sync_update(&prioritization_fee_cache, bank.clone(), txs.iter());
+            sync_finalize_priority_fee_for_test(&prioritization_fee_cache, slot, bank.bank_id());
+        };
+
+        add_block(1, 3);
+        add_block(2, 1);
+        let fees = prioritization_fee_cache.get_prioritization_fees_owned();
+        assert_eq!(vec![3, 1], *fees);
+
+        // later cache mutations do not affect the returned snapshot
+        add_block(3, 8);
+        prioritization_fee_cache.abandon_slot(1);
+        assert_eq!(vec![3, 1], *fees);
+        assert_eq!(
+            vec![1, 8],
+            *prioritization_fee_cache.get_prioritization_fees_owned()
+        );
+    }
+
+    #[test]
+    fn test_write_to_read_from() {
+        solana_logger::setup();
+        let write_account_a = Pubkey::new_unique();
+        let write_account_b = Pubkey::new_unique();
+
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(10_000);
+        let bank0 = Bank::new_for_benches(&genesis_config);
+        let bank_forks = BankForks::new_rw_arc(bank0);
+        let bank = bank_forks.read().unwrap().working_bank();
+        let collector = solana_pubkey::new_rand();
+
+        let source_cache = PrioritizationFeeCache::default();
+        for (slot, fee) in [(1, 4), (2, 7)] {
+            let bank = Arc::new(Bank::new_from_parent(bank.clone(), &collector, slot));
+            let txs = [build_sanitized_transaction_for_test(
+                fee,
+                &write_account_a,
+                &write_account_b,
+            )];
+            sync_update(&source_cache, bank.clone(), txs.iter());
+            sync_finalize_priority_fee_for_test(&source_cache, slot, bank.bank_id());
+        }
+
+        let mut buffer = vec![];
+        source_cache.write_to(&mut buffer).unwrap();
+
+        let target_cache = PrioritizationFeeCache::default();
+        target_cache.read_from(&mut buffer.as_slice()).unwrap();
+        assert_eq!(source_cache.snapshot(), target_cache.snapshot());
+        assert_eq!(
+            source_cache.get_prioritization_fees(&[write_account_a]),
+            target_cache.get_prioritization_fees(&[write_account_a])
+        );
+
+        // truncated input is rejected and leaves the cache untouched
+        let empty_cache = PrioritizationFeeCache::default();
+        let err = empty_cache
+            .read_from(&mut &buffer[..buffer.len() - 1])
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(0, empty_cache.available_block_count());
+    }
 }
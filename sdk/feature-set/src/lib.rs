@@ -920,6 +920,10 @@ pub mod raise_block_limits_to_50m {
     solana_pubkey::declare_id!("5oMCU3JPaFLr8Zr4ct7yFA7jdk6Mw1RmB8K4u9ZbS42z");
 }
 
+pub mod default_zero_loaded_accounts_data_size_limit {
+    solana_pubkey::declare_id!("E4Zfjs26aRWjM6scVkKSRYDsr7SjPrHP1ctUhhrVp2kS");
+}
+
 lazy_static! {
     /// Map of feature identifiers to user-visible description
     pub static ref FEATURE_NAMES: AHashMap<Pubkey, &'static str> = [
@@ -1145,6 +1149,7 @@ lazy_static! {
         (deplete_cu_meter_on_vm_failure::id(), "Deplete compute meter for vm errors SIMD-0182 #3993"),
         (reserve_minimal_cus_for_builtin_instructions::id(), "Reserve minimal CUs for builtin instructions SIMD-170 #2562"),
         (raise_block_limits_to_50m::id(), "Raise block limit to 50M SIMD-0207"),
+        (default_zero_loaded_accounts_data_size_limit::id(), "Treat zero loaded accounts data size limit as default limit"),
         /*************** ADD NEW FEATURES HERE ***************/
     ]
     .iter()
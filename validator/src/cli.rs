@@ -1599,6 +1599,16 @@ pub fn app<'a>(version: &'a str, default_args: &'a DefaultArgs) -> App<'a, 'a> {
                 .possible_values(BlockProductionMethod::cli_names())
                 .help(BlockProductionMethod::cli_message()),
         )
+        .arg(
+            Arg::with_name("banking_order_by_total_priority_fee")
+                .long("banking-order-by-total-priority-fee")
+                .hidden(hidden_unless_forced())
+                .takes_value(false)
+                .help(
+                    "Order transactions buffered by the banking stage by their total \
+                    prioritization fee, rather than by reward per unit of cost.",
+                ),
+        )
         .arg(
             Arg::with_name("unified_scheduler_handler_threads")
                 .long("unified-scheduler-handler-threads")
@@ -1861,6 +1861,8 @@ pub fn main() {
     )
     .unwrap_or_default();
     validator_config.enable_block_production_forwarding = staked_nodes_overrides_path.is_some();
+    validator_config.banking_order_by_total_priority_fee =
+        matches.is_present("banking_order_by_total_priority_fee");
     validator_config.unified_scheduler_handler_threads =
         value_t!(matches, "unified_scheduler_handler_threads", usize).ok();
 